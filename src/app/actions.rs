@@ -0,0 +1,639 @@
+use std::collections::BTreeMap;
+
+use druid::commands::{self, SHOW_OPEN_PANEL};
+use druid::{Command, FileDialogOptions, FileSpec};
+
+use crate::case::Case;
+use crate::editor::{
+    ADD_CARET_ABOVE, ADD_CARET_BELOW, FOLD_ALL, GO_TO_MATCHING_BRACKET, SELECT_TO_MATCHING_BRACKET,
+    UNFOLD_ALL,
+};
+use crate::keymap::Keymap;
+use crate::lines::{LineOperation, SortKey};
+
+use super::commands::{
+    CMD_ADD_NEXT_OCCURRENCE, CMD_CLEAR_RECENT, CMD_CLOSE_TAB, CMD_CONVERT_CASE,
+    CMD_CONVERT_INDENTATION, CMD_DECREASE_FONT, CMD_EXIT, CMD_FIND_NEXT, CMD_FIND_PREV, CMD_GO_TO,
+    CMD_HARD_WRAP, CMD_INCREASE_FONT, CMD_LINE_OPERATION, CMD_NEW_FILE, CMD_NEXT_TAB, CMD_PREV_TAB,
+    CMD_REPLACE_ALL, CMD_REPLACE_ONE, CMD_SAVE_AS, CMD_SAVE_FILE, CMD_SHOW_ABOUT,
+    CMD_SHOW_FONT_PICKER, CMD_SHOW_PALETTE, CMD_SHOW_SEARCH, CMD_TIME_DATE, CMD_TOGGLE_COMMENT,
+    CMD_TOGGLE_CURRENT_LINE, CMD_TOGGLE_INSERT_SPACES, CMD_TOGGLE_LINE_NUMBERS, CMD_TOGGLE_STATUS,
+    CMD_TOGGLE_WHITESPACE, CMD_TOGGLE_WRAP, CMD_TOGGLE_WRAP_COLUMN, CMD_ZOOM_IN, CMD_ZOOM_OUT,
+    CMD_ZOOM_RESET,
+};
+use super::state::SearchMode;
+
+/// A user-facing command: what the palette lists and what can be bound to
+/// keys, addressed by a stable id that appears in the keymap file.
+pub struct Action {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default_keys: &'static str,
+    command: fn() -> Command,
+}
+
+impl Action {
+    pub fn command(&self) -> Command {
+        (self.command)()
+    }
+}
+
+pub const ACTIONS: &[Action] = &[
+    Action {
+        id: "new-file",
+        name: "New File",
+        description: "Open an empty untitled tab",
+        default_keys: "Cmd+N",
+        command: || CMD_NEW_FILE.into(),
+    },
+    Action {
+        id: "open-file",
+        name: "Open File...",
+        description: "Choose a file to open in a new tab",
+        default_keys: "Cmd+O",
+        command: open_file,
+    },
+    Action {
+        id: "save",
+        name: "Save",
+        description: "Write the current tab to disk",
+        default_keys: "Cmd+S",
+        command: || CMD_SAVE_FILE.into(),
+    },
+    Action {
+        id: "save-as",
+        name: "Save As...",
+        description: "Save the current tab under a new name",
+        default_keys: "Cmd+Shift+F",
+        command: || CMD_SAVE_AS.into(),
+    },
+    Action {
+        id: "close-tab",
+        name: "Close Tab",
+        description: "Close the current tab",
+        default_keys: "Cmd+W",
+        command: || CMD_CLOSE_TAB.into(),
+    },
+    Action {
+        id: "clear-recent",
+        name: "Clear Recent Files",
+        description: "Forget the Open Recent list",
+        default_keys: "",
+        command: || CMD_CLEAR_RECENT.into(),
+    },
+    Action {
+        id: "exit",
+        name: "Exit",
+        description: "Quit RustPad",
+        default_keys: "",
+        command: || CMD_EXIT.into(),
+    },
+    Action {
+        id: "undo",
+        name: "Undo",
+        description: "Revert the last edit",
+        default_keys: "Cmd+Z",
+        command: || commands::UNDO.into(),
+    },
+    Action {
+        id: "redo",
+        name: "Redo",
+        description: "Reapply the last undone edit",
+        default_keys: "Cmd+Shift+Z",
+        command: || commands::REDO.into(),
+    },
+    Action {
+        id: "cut",
+        name: "Cut",
+        description: "Move the selection to the clipboard",
+        default_keys: "Cmd+X",
+        command: || commands::CUT.into(),
+    },
+    Action {
+        id: "copy",
+        name: "Copy",
+        description: "Copy the selection to the clipboard",
+        default_keys: "Cmd+C",
+        command: || commands::COPY.into(),
+    },
+    Action {
+        id: "paste",
+        name: "Paste",
+        description: "Insert the clipboard contents",
+        default_keys: "Cmd+V",
+        command: || commands::PASTE.into(),
+    },
+    Action {
+        id: "select-all",
+        name: "Select All",
+        description: "Select the whole document",
+        default_keys: "Cmd+A",
+        command: || commands::SELECT_ALL.into(),
+    },
+    Action {
+        id: "add-next-occurrence",
+        name: "Add Next Occurrence",
+        description: "Add a caret at the next match of the selection, or select the word",
+        default_keys: "Cmd+D",
+        command: || CMD_ADD_NEXT_OCCURRENCE.into(),
+    },
+    Action {
+        id: "add-caret-above",
+        name: "Add Caret Above",
+        description: "Add a caret on the line above the topmost caret",
+        default_keys: "Alt+Shift+Up",
+        command: || ADD_CARET_ABOVE.into(),
+    },
+    Action {
+        id: "add-caret-below",
+        name: "Add Caret Below",
+        description: "Add a caret on the line below the last caret",
+        default_keys: "Alt+Shift+Down",
+        command: || ADD_CARET_BELOW.into(),
+    },
+    Action {
+        id: "duplicate-line",
+        name: "Duplicate Line/Selection",
+        description: "Duplicate the selection, or the caret's line",
+        default_keys: "Cmd+Shift+D",
+        command: || CMD_LINE_OPERATION.with(LineOperation::Duplicate),
+    },
+    Action {
+        id: "move-line-up",
+        name: "Move Line Up",
+        description: "Move the selected lines above the line before them",
+        default_keys: "Alt+Up",
+        command: || CMD_LINE_OPERATION.with(LineOperation::MoveUp),
+    },
+    Action {
+        id: "move-line-down",
+        name: "Move Line Down",
+        description: "Move the selected lines below the line after them",
+        default_keys: "Alt+Down",
+        command: || CMD_LINE_OPERATION.with(LineOperation::MoveDown),
+    },
+    Action {
+        id: "delete-line",
+        name: "Delete Line",
+        description: "Delete the selected lines",
+        default_keys: "Cmd+Shift+K",
+        command: || CMD_LINE_OPERATION.with(LineOperation::Delete),
+    },
+    Action {
+        id: "join-lines",
+        name: "Join Lines",
+        description: "Join the selected lines, or the caret's line and the next",
+        default_keys: "Cmd+J",
+        command: || CMD_LINE_OPERATION.with(LineOperation::Join),
+    },
+    Action {
+        id: "insert-line-above",
+        name: "Insert Line Above",
+        description: "Start a new line above the caret's line",
+        default_keys: "Cmd+Shift+Enter",
+        command: || CMD_LINE_OPERATION.with(LineOperation::InsertAbove),
+    },
+    Action {
+        id: "insert-line-below",
+        name: "Insert Line Below",
+        description: "Start a new line below the caret's line",
+        default_keys: "Cmd+Enter",
+        command: || CMD_LINE_OPERATION.with(LineOperation::InsertBelow),
+    },
+    Action {
+        id: "toggle-line-comment",
+        name: "Toggle Line Comment",
+        description: "Comment or uncomment the selected lines",
+        default_keys: "Cmd+/",
+        command: || CMD_TOGGLE_COMMENT.with(false),
+    },
+    Action {
+        id: "toggle-block-comment",
+        name: "Toggle Block Comment",
+        description: "Wrap the selected lines in a block comment, or unwrap them",
+        // Shift+/ arrives as "?" on US layouts.
+        default_keys: "Cmd+Shift+?",
+        command: || CMD_TOGGLE_COMMENT.with(true),
+    },
+    Action {
+        id: "sort-lines-ascending",
+        name: "Sort Lines Ascending",
+        description: "Sort the selected lines, or every line, A to Z",
+        default_keys: "",
+        command: || {
+            CMD_LINE_OPERATION.with(LineOperation::Sort {
+                key: SortKey::Text,
+                descending: false,
+            })
+        },
+    },
+    Action {
+        id: "sort-lines-descending",
+        name: "Sort Lines Descending",
+        description: "Sort the selected lines, or every line, Z to A",
+        default_keys: "",
+        command: || {
+            CMD_LINE_OPERATION.with(LineOperation::Sort {
+                key: SortKey::Text,
+                descending: true,
+            })
+        },
+    },
+    Action {
+        id: "sort-lines-case-insensitive",
+        name: "Sort Lines Ignoring Case",
+        description: "Sort the selected lines, or every line, ignoring case",
+        default_keys: "",
+        command: || {
+            CMD_LINE_OPERATION.with(LineOperation::Sort {
+                key: SortKey::CaseInsensitive,
+                descending: false,
+            })
+        },
+    },
+    Action {
+        id: "sort-lines-natural",
+        name: "Sort Lines Naturally",
+        description: "Sort the selected lines, or every line, with numbers by value",
+        default_keys: "",
+        command: || {
+            CMD_LINE_OPERATION.with(LineOperation::Sort {
+                key: SortKey::Natural,
+                descending: false,
+            })
+        },
+    },
+    Action {
+        id: "sort-lines-by-column",
+        name: "Sort Lines by Column...",
+        description: "Sort lines by a delimited field",
+        default_keys: "",
+        command: || CMD_SHOW_SEARCH.with(SearchMode::SortLines),
+    },
+    Action {
+        id: "remove-duplicate-lines",
+        name: "Remove Duplicate Lines",
+        description: "Keep only the first of each repeated line",
+        default_keys: "",
+        command: || CMD_LINE_OPERATION.with(LineOperation::RemoveDuplicates),
+    },
+    Action {
+        id: "remove-blank-lines",
+        name: "Remove Blank Lines",
+        description: "Remove lines that are empty or only whitespace",
+        default_keys: "",
+        command: || CMD_LINE_OPERATION.with(LineOperation::RemoveBlank),
+    },
+    Action {
+        id: "shuffle-lines",
+        name: "Shuffle Lines",
+        description: "Put the selected lines, or every line, in random order",
+        default_keys: "",
+        command: || CMD_LINE_OPERATION.with(LineOperation::Shuffle),
+    },
+    Action {
+        id: "reverse-lines",
+        name: "Reverse Lines",
+        description: "Reverse the order of the selected lines, or every line",
+        default_keys: "",
+        command: || CMD_LINE_OPERATION.with(LineOperation::Reverse),
+    },
+    Action {
+        id: "find",
+        name: "Find...",
+        description: "Show the find panel",
+        default_keys: "Cmd+F",
+        command: || CMD_SHOW_SEARCH.with(SearchMode::Find),
+    },
+    Action {
+        id: "find-next",
+        name: "Find Next",
+        description: "Jump to the next match",
+        default_keys: "F3",
+        command: || CMD_FIND_NEXT.into(),
+    },
+    Action {
+        id: "find-previous",
+        name: "Find Previous",
+        description: "Jump to the previous match",
+        default_keys: "Shift+F3",
+        command: || CMD_FIND_PREV.into(),
+    },
+    Action {
+        id: "replace",
+        name: "Replace...",
+        description: "Show the replace panel",
+        default_keys: "Cmd+H",
+        command: || CMD_SHOW_SEARCH.with(SearchMode::Replace),
+    },
+    Action {
+        id: "replace-next",
+        name: "Replace Next",
+        description: "Replace the current match and find the next",
+        default_keys: "",
+        command: || CMD_REPLACE_ONE.into(),
+    },
+    Action {
+        id: "replace-all",
+        name: "Replace All",
+        description: "Replace every match in the document",
+        default_keys: "",
+        command: || CMD_REPLACE_ALL.into(),
+    },
+    Action {
+        id: "go-to",
+        name: "Go To...",
+        description: "Show the go to line panel",
+        default_keys: "Cmd+G",
+        command: || CMD_SHOW_SEARCH.with(SearchMode::GoTo),
+    },
+    Action {
+        id: "go-to-line",
+        name: "Go To Line",
+        description: "Jump to the line entered in the go to panel",
+        default_keys: "",
+        command: || CMD_GO_TO.into(),
+    },
+    Action {
+        id: "go-to-matching-bracket",
+        name: "Go to Matching Bracket",
+        description: "Move the caret to the partner of the bracket next to it",
+        default_keys: "Cmd+M",
+        command: || GO_TO_MATCHING_BRACKET.into(),
+    },
+    Action {
+        id: "select-to-matching-bracket",
+        name: "Select to Matching Bracket",
+        description: "Select from the bracket next to the caret to its partner",
+        default_keys: "Cmd+Shift+M",
+        command: || SELECT_TO_MATCHING_BRACKET.into(),
+    },
+    Action {
+        id: "insert-time-date",
+        name: "Insert Time/Date",
+        description: "Insert the current time and date",
+        default_keys: "Cmd+Shift+T",
+        command: || CMD_TIME_DATE.into(),
+    },
+    Action {
+        id: "toggle-word-wrap",
+        name: "Toggle Word Wrap",
+        description: "Wrap long lines to the window width",
+        default_keys: "",
+        command: || CMD_TOGGLE_WRAP.into(),
+    },
+    Action {
+        id: "toggle-wrap-at-column",
+        name: "Toggle Wrap at Column",
+        description: "Wrap long lines at the configured column",
+        default_keys: "",
+        command: || CMD_TOGGLE_WRAP_COLUMN.into(),
+    },
+    Action {
+        id: "hard-wrap-selection",
+        name: "Hard Wrap Selection",
+        description: "Reflow the selected paragraphs to the ruler width",
+        default_keys: "",
+        command: || CMD_HARD_WRAP.into(),
+    },
+    Action {
+        id: "convert-to-uppercase",
+        name: "Convert to UPPERCASE",
+        description: "Convert the selection to upper case",
+        default_keys: "Cmd+Shift+U",
+        command: || CMD_CONVERT_CASE.with(Case::Upper),
+    },
+    Action {
+        id: "convert-to-lowercase",
+        name: "Convert to lowercase",
+        description: "Convert the selection to lower case",
+        default_keys: "Cmd+U",
+        command: || CMD_CONVERT_CASE.with(Case::Lower),
+    },
+    Action {
+        id: "convert-to-title-case",
+        name: "Convert to Title Case",
+        description: "Capitalize every word of the selection",
+        default_keys: "",
+        command: || CMD_CONVERT_CASE.with(Case::Title),
+    },
+    Action {
+        id: "convert-to-sentence-case",
+        name: "Convert to Sentence case",
+        description: "Capitalize the first word of each sentence in the selection",
+        default_keys: "",
+        command: || CMD_CONVERT_CASE.with(Case::Sentence),
+    },
+    Action {
+        id: "convert-to-snake-case",
+        name: "Convert to snake_case",
+        description: "Join the words of the selection with underscores",
+        default_keys: "",
+        command: || CMD_CONVERT_CASE.with(Case::Snake),
+    },
+    Action {
+        id: "convert-to-camel-case",
+        name: "Convert to camelCase",
+        description: "Join the words of the selection, capitalizing all but the first",
+        default_keys: "",
+        command: || CMD_CONVERT_CASE.with(Case::Camel),
+    },
+    Action {
+        id: "convert-to-kebab-case",
+        name: "Convert to kebab-case",
+        description: "Join the words of the selection with hyphens",
+        default_keys: "",
+        command: || CMD_CONVERT_CASE.with(Case::Kebab),
+    },
+    Action {
+        id: "invert-case",
+        name: "Invert Case",
+        description: "Swap upper and lower case in the selection",
+        default_keys: "",
+        command: || CMD_CONVERT_CASE.with(Case::Invert),
+    },
+    Action {
+        id: "toggle-insert-spaces",
+        name: "Toggle Insert Spaces",
+        description: "Indent with spaces instead of tabs, or back",
+        default_keys: "",
+        command: || CMD_TOGGLE_INSERT_SPACES.into(),
+    },
+    Action {
+        id: "convert-indentation-to-spaces",
+        name: "Convert Indentation to Spaces",
+        description: "Rewrite every line's indentation with spaces",
+        default_keys: "",
+        command: || CMD_CONVERT_INDENTATION.with(false),
+    },
+    Action {
+        id: "convert-indentation-to-tabs",
+        name: "Convert Indentation to Tabs",
+        description: "Rewrite every line's indentation with tabs",
+        default_keys: "",
+        command: || CMD_CONVERT_INDENTATION.with(true),
+    },
+    Action {
+        id: "increase-font-size",
+        name: "Increase Font Size",
+        description: "Make the editor text larger",
+        default_keys: "Cmd+=",
+        command: || CMD_INCREASE_FONT.into(),
+    },
+    Action {
+        id: "decrease-font-size",
+        name: "Decrease Font Size",
+        description: "Make the editor text smaller",
+        default_keys: "Cmd+-",
+        command: || CMD_DECREASE_FONT.into(),
+    },
+    Action {
+        id: "zoom-in",
+        name: "Zoom In",
+        description: "Magnify the editor text",
+        default_keys: "Cmd+Alt+=",
+        command: || CMD_ZOOM_IN.into(),
+    },
+    Action {
+        id: "zoom-out",
+        name: "Zoom Out",
+        description: "Shrink the editor text",
+        default_keys: "Cmd+Alt+-",
+        command: || CMD_ZOOM_OUT.into(),
+    },
+    Action {
+        id: "zoom-reset",
+        name: "Reset Zoom",
+        description: "Return to 100% zoom",
+        default_keys: "Cmd+0",
+        command: || CMD_ZOOM_RESET.into(),
+    },
+    Action {
+        id: "toggle-status-bar",
+        name: "Toggle Status Bar",
+        description: "Show or hide the status bar",
+        default_keys: "",
+        command: || CMD_TOGGLE_STATUS.into(),
+    },
+    Action {
+        id: "toggle-line-numbers",
+        name: "Toggle Line Numbers",
+        description: "Show or hide the line number gutter",
+        default_keys: "",
+        command: || CMD_TOGGLE_LINE_NUMBERS.into(),
+    },
+    Action {
+        id: "toggle-current-line",
+        name: "Toggle Current Line Highlight",
+        description: "Shade the line the caret is on",
+        default_keys: "",
+        command: || CMD_TOGGLE_CURRENT_LINE.into(),
+    },
+    Action {
+        id: "toggle-whitespace",
+        name: "Toggle Whitespace",
+        description: "Show spaces, tabs and line ends as faint marks",
+        default_keys: "",
+        command: || CMD_TOGGLE_WHITESPACE.into(),
+    },
+    Action {
+        id: "fold-all",
+        name: "Fold All",
+        description: "Collapse every indented or bracketed block",
+        default_keys: "Cmd+K Cmd+[",
+        command: || FOLD_ALL.into(),
+    },
+    Action {
+        id: "unfold-all",
+        name: "Unfold All",
+        description: "Expand every folded block",
+        default_keys: "Cmd+K Cmd+]",
+        command: || UNFOLD_ALL.into(),
+    },
+    Action {
+        id: "next-tab",
+        name: "Next Tab",
+        description: "Switch to the tab on the right",
+        default_keys: "Cmd+Tab",
+        command: || CMD_NEXT_TAB.into(),
+    },
+    Action {
+        id: "previous-tab",
+        name: "Previous Tab",
+        description: "Switch to the tab on the left",
+        default_keys: "Cmd+Shift+Tab",
+        command: || CMD_PREV_TAB.into(),
+    },
+    Action {
+        id: "choose-font",
+        name: "Font...",
+        description: "Pick the editor font family, style and size",
+        default_keys: "",
+        command: || CMD_SHOW_FONT_PICKER.into(),
+    },
+    Action {
+        id: "command-palette",
+        name: "Command Palette",
+        description: "Search for a command by name",
+        default_keys: "Cmd+Shift+P",
+        command: || CMD_SHOW_PALETTE.into(),
+    },
+    Action {
+        id: "about",
+        name: "About RustPad",
+        description: "Show version information",
+        default_keys: "",
+        command: || CMD_SHOW_ABOUT.into(),
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|action| action.id == id)
+}
+
+pub fn default_bindings() -> Vec<(&'static str, &'static str)> {
+    ACTIONS
+        .iter()
+        .map(|action| (action.id, action.default_keys))
+        .collect()
+}
+
+pub fn default_keymap() -> Keymap {
+    Keymap::resolve(&default_bindings(), &BTreeMap::new()).0
+}
+
+fn open_file() -> Command {
+    let options = FileDialogOptions::new()
+        .allowed_types(vec![FileSpec::new("Text", &["txt", "md", "rs", ""])])
+        .name_label("Document")
+        .title("Open");
+    SHOW_OPEN_PANEL.with(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Binding;
+
+    /// Menus take their accelerators before the keymap sees the key, so the
+    /// second chord of a sequence must not be another action's single key.
+    #[test]
+    fn sequences_do_not_end_in_menu_accelerators() {
+        let bindings: Vec<(&str, Binding)> = default_bindings()
+            .into_iter()
+            .filter(|(_, keys)| !keys.is_empty())
+            .map(|(id, keys)| (id, keys.parse().unwrap()))
+            .collect();
+        for (id, binding) in &bindings {
+            for (other, single) in &bindings {
+                if let [chord] = single.chords() {
+                    assert!(
+                        !binding.chords()[1..].contains(chord),
+                        "{id} ({binding}) ends in {other} ({single})"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,23 +1,60 @@
+use std::sync::Arc;
+
 use druid::{Selector, WidgetId};
 
-use super::state::{FontChoice, SearchMode};
+use super::state::{FontSettings, PromptChoice, SearchMode};
+use crate::case::Case;
+use crate::lines::LineOperation;
 
 pub const CMD_NEW_FILE: Selector<()> = Selector::new("rustpad.cmd.new");
+pub const CMD_OPEN_RECENT: Selector<Arc<String>> = Selector::new("rustpad.cmd.open-recent");
+pub const CMD_CLEAR_RECENT: Selector<()> = Selector::new("rustpad.cmd.clear-recent");
 pub const CMD_SAVE_FILE: Selector<()> = Selector::new("rustpad.cmd.save");
 pub const CMD_SAVE_AS: Selector<()> = Selector::new("rustpad.cmd.save-as");
 pub const CMD_EXIT: Selector<()> = Selector::new("rustpad.cmd.exit");
 pub const CMD_TIME_DATE: Selector<()> = Selector::new("rustpad.cmd.time-date");
 pub const CMD_TOGGLE_WRAP: Selector<()> = Selector::new("rustpad.cmd.wrap");
+pub const CMD_TOGGLE_WRAP_COLUMN: Selector<()> = Selector::new("rustpad.cmd.wrap-column");
+pub const CMD_HARD_WRAP: Selector<()> = Selector::new("rustpad.cmd.hard-wrap");
+pub const CMD_LINE_OPERATION: Selector<LineOperation> = Selector::new("rustpad.cmd.line-operation");
+/// Sorts lines by the column set in the sort panel; the payload is `descending`.
+pub const CMD_SORT_BY_COLUMN: Selector<bool> = Selector::new("rustpad.cmd.sort-by-column");
+pub const CMD_CONVERT_CASE: Selector<Case> = Selector::new("rustpad.cmd.convert-case");
+pub const CMD_TOGGLE_INSERT_SPACES: Selector<()> = Selector::new("rustpad.cmd.insert-spaces");
+pub const CMD_SET_TAB_WIDTH: Selector<u8> = Selector::new("rustpad.cmd.set-tab-width");
+/// Rewrites the document's indentation; the payload is `true` for tabs.
+pub const CMD_CONVERT_INDENTATION: Selector<bool> =
+    Selector::new("rustpad.cmd.convert-indentation");
+/// Comments or uncomments the selected lines; the payload is `true` for a
+/// block comment.
+pub const CMD_TOGGLE_COMMENT: Selector<bool> = Selector::new("rustpad.cmd.toggle-comment");
+pub const CMD_ADD_NEXT_OCCURRENCE: Selector<()> = Selector::new("rustpad.cmd.add-next-occurrence");
 pub const CMD_TOGGLE_STATUS: Selector<()> = Selector::new("rustpad.cmd.status");
+pub const CMD_TOGGLE_LINE_NUMBERS: Selector<()> = Selector::new("rustpad.cmd.line-numbers");
+pub const CMD_TOGGLE_CURRENT_LINE: Selector<()> = Selector::new("rustpad.cmd.current-line");
+pub const CMD_TOGGLE_WHITESPACE: Selector<()> = Selector::new("rustpad.cmd.whitespace");
 pub const CMD_SHOW_ABOUT: Selector<()> = Selector::new("rustpad.cmd.about");
+pub const CMD_SHOW_PALETTE: Selector<()> = Selector::new("rustpad.cmd.show-palette");
 pub const CMD_SHOW_SEARCH: Selector<SearchMode> = Selector::new("rustpad.cmd.show-search");
 pub const CMD_FIND_NEXT: Selector<()> = Selector::new("rustpad.cmd.find-next");
 pub const CMD_FIND_PREV: Selector<()> = Selector::new("rustpad.cmd.find-prev");
 pub const CMD_REPLACE_ONE: Selector<()> = Selector::new("rustpad.cmd.replace-one");
 pub const CMD_REPLACE_ALL: Selector<()> = Selector::new("rustpad.cmd.replace-all");
 pub const CMD_GO_TO: Selector<()> = Selector::new("rustpad.cmd.goto");
-pub const CMD_SET_FONT: Selector<FontChoice> = Selector::new("rustpad.cmd.font-choice");
+pub const CMD_SHOW_FONT_PICKER: Selector<()> = Selector::new("rustpad.cmd.show-font-picker");
+pub const CMD_SET_FONT: Selector<FontSettings> = Selector::new("rustpad.cmd.set-font");
+pub const CMD_SET_THEME: Selector<String> = Selector::new("rustpad.cmd.set-theme");
 pub const CMD_INCREASE_FONT: Selector<()> = Selector::new("rustpad.cmd.font-increase");
 pub const CMD_DECREASE_FONT: Selector<()> = Selector::new("rustpad.cmd.font-decrease");
+pub const CMD_ZOOM_IN: Selector<()> = Selector::new("rustpad.cmd.zoom-in");
+pub const CMD_ZOOM_OUT: Selector<()> = Selector::new("rustpad.cmd.zoom-out");
+pub const CMD_ZOOM_RESET: Selector<()> = Selector::new("rustpad.cmd.zoom-reset");
+pub const CMD_CLOSE_TAB: Selector<()> = Selector::new("rustpad.cmd.close-tab");
+pub const CMD_NEXT_TAB: Selector<()> = Selector::new("rustpad.cmd.next-tab");
+pub const CMD_PREV_TAB: Selector<()> = Selector::new("rustpad.cmd.prev-tab");
+pub const CMD_PROMPT_RESPONSE: Selector<PromptChoice> =
+    Selector::new("rustpad.cmd.prompt-response");
+pub const CMD_AUTOSAVE_FAILED: Selector<String> = Selector::new("rustpad.cmd.autosave-failed");
+pub const CMD_FONTS_LOADED: Selector<()> = Selector::new("rustpad.cmd.fonts-loaded");
 
 pub const EDITOR_ID: WidgetId = WidgetId::reserved(1);
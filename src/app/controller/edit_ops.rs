@@ -1,11 +1,22 @@
+use std::sync::Arc;
+
 use chrono::Local;
-use druid::EventCtx;
+use druid::{Data, EventCtx};
 
 use crate::app::commands::EDITOR_ID;
-use crate::app::state::{AppState, FontChoice};
+use crate::app::state::{AppState, FontPickerState, FontSettings};
 use crate::editor::{APPLY_SELECTION, ByteRange, CharRange, SelectionState};
 
-use super::text_utils::char_to_byte;
+use crate::case::{self, Case};
+use crate::comment::{self, CommentSyntax};
+use crate::fonts;
+use crate::indent::{self, IndentStyle, Indentation};
+use crate::lines::{self, LineOperation};
+use crate::reflow::{self, Style};
+use crate::search::{SearchRequest, find_forward};
+use crate::syntax::Language;
+
+use super::text_utils::{char_to_byte, full_lines, word_at};
 
 pub fn insert_timestamp(ctx: &mut EventCtx, data: &mut AppState) {
     let stamp = Local::now().format("%I:%M %p %m/%d/%Y").to_string();
@@ -14,9 +25,6 @@ pub fn insert_timestamp(ctx: &mut EventCtx, data: &mut AppState) {
 
 pub fn toggle_wrap(data: &mut AppState) {
     data.word_wrap = !data.word_wrap;
-    if data.word_wrap {
-        data.show_status_bar = false;
-    }
 }
 
 pub fn toggle_status_bar(data: &mut AppState) {
@@ -29,12 +37,21 @@ pub fn show_about(data: &mut AppState) {
     data.info_message = Some("RustPad\nA retro-inspired editor built with Druid.".to_string());
 }
 
-pub fn set_font_choice(data: &mut AppState, choice: FontChoice) -> bool {
-    if data.font.choice != choice {
-        data.font.choice = choice;
-        true
-    } else {
+pub fn show_font_picker(data: &mut AppState) {
+    data.font_picker = FontPickerState {
+        visible: true,
+        filter: String::new(),
+        loaded: fonts::installed_families().is_some(),
+        draft: data.font.clone(),
+    };
+}
+
+pub fn set_font(data: &mut AppState, font: &FontSettings) -> bool {
+    if data.font.same(font) {
         false
+    } else {
+        data.font = font.clone();
+        true
     }
 }
 
@@ -47,9 +64,7 @@ pub fn decrease_font_size(data: &mut AppState) -> bool {
 }
 
 fn adjust_font_size(data: &mut AppState, delta: f64) -> bool {
-    const MIN_FONT_SIZE: f64 = 8.0;
-    const MAX_FONT_SIZE: f64 = 48.0;
-    let new_size = (data.font.size + delta).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+    let new_size = (data.font.size + delta).clamp(FontSettings::MIN_SIZE, FontSettings::MAX_SIZE);
     if (new_size - data.font.size).abs() > f64::EPSILON {
         data.font.size = new_size;
         true
@@ -58,24 +73,240 @@ fn adjust_font_size(data: &mut AppState, delta: f64) -> bool {
     }
 }
 
+/// Steps the zoom level; `steps` may be negative. Returns whether it changed.
+pub fn zoom(data: &mut AppState, steps: i32) -> bool {
+    let zoom = i32::from(data.zoom_percent) + steps * i32::from(AppState::ZOOM_STEP);
+    let zoom = zoom.clamp(i32::from(AppState::MIN_ZOOM), i32::from(AppState::MAX_ZOOM)) as u16;
+    set_zoom(data, zoom)
+}
+
+pub fn set_zoom(data: &mut AppState, zoom_percent: u16) -> bool {
+    if data.zoom_percent == zoom_percent {
+        false
+    } else {
+        data.zoom_percent = zoom_percent;
+        true
+    }
+}
+
+/// Reflows the selected lines (or the caret's line) to the first ruler, or to
+/// the wrap column when there are no rulers.
+pub fn hard_wrap_selection(ctx: &mut EventCtx, data: &mut AppState) {
+    let width = data.rulers.first().copied().unwrap_or(data.wrap_column);
+    let doc = data.doc();
+    let range = full_lines(&doc.text, doc.selection.byte_range);
+    let style = match Language::from_path(doc.pathbuf().as_deref()) {
+        Language::Plain | Language::Markdown => Style::Prose,
+        language => Style::Code {
+            line_comment: CommentSyntax::for_language(language).and_then(|syntax| syntax.line),
+        },
+    };
+    let wrapped = reflow::hard_wrap(&doc.text[range.start..range.end], usize::from(width), style);
+    replace_range(ctx, data, range, &wrapped);
+}
+
+/// Converts the selection, or the word at the caret, and leaves the result
+/// selected.
+pub fn convert_case(ctx: &mut EventCtx, data: &mut AppState, target: Case) {
+    let doc = data.doc();
+    let mut range = doc.selection.byte_range;
+    if range.start == range.end {
+        range = word_at(&doc.text, range.start);
+    }
+    let converted = case::convert(&doc.text[range.start..range.end], target);
+    replace_range(ctx, data, range, &converted);
+}
+
+/// Switches the active document between tabs and spaces, and makes that the
+/// default for new documents.
+pub fn toggle_insert_spaces(data: &mut AppState) {
+    data.insert_spaces = data.indentation().tabs;
+    data.doc_mut().indent_style = None;
+}
+
+/// Sets the tab width, which is also the indent for documents indented with
+/// spaces unless one was detected with another width.
+pub fn set_tab_width(data: &mut AppState, width: u8) {
+    data.tab_width = width;
+}
+
+/// Rewrites the whole document's indentation with tabs or spaces, one level
+/// for one level, and keeps indenting that way.
+pub fn convert_indentation(ctx: &mut EventCtx, data: &mut AppState, tabs: bool) {
+    let width = data.indentation().width;
+    let doc = data.doc();
+    let caret = doc.selection.byte_range.end;
+    let (converted, caret) = indent::convert(&doc.text, Indentation { tabs, width }, caret);
+    let range = ByteRange {
+        start: 0,
+        end: doc.text.len(),
+    };
+    data.doc_mut().indent_style = Some(if tabs {
+        IndentStyle::Tabs
+    } else {
+        IndentStyle::Spaces(width)
+    });
+    let caret = ByteRange {
+        start: caret,
+        end: caret,
+    };
+    replace_and_select(ctx, data, range, &converted, caret);
+}
+
+/// Comments or uncomments the selected lines in the document's language.
+pub fn toggle_comment(ctx: &mut EventCtx, data: &mut AppState, block: bool) {
+    let doc = data.doc();
+    let language = Language::from_path(doc.pathbuf().as_deref());
+    let Some(syntax) = CommentSyntax::for_language(language) else {
+        data.info_message = Some("This file type has no comment syntax.".to_string());
+        return;
+    };
+    let selection = doc.selection.byte_range;
+    let range = selection.start..selection.end;
+    let edit = if block {
+        comment::toggle_block(&doc.text, range, syntax)
+    } else {
+        comment::toggle_line(&doc.text, range, syntax)
+    };
+    if let Some(edit) = edit {
+        apply_edit(ctx, data, edit);
+    }
+}
+
+/// Selects the word at the caret; with text selected, adds a caret at its
+/// next occurrence instead, wrapping past the end of the document.
+pub fn add_next_occurrence(ctx: &mut EventCtx, data: &mut AppState) {
+    let doc = data.doc();
+    let selection = doc.selection.byte_range;
+    let target = if selection.start == selection.end {
+        let word = word_at(&doc.text, selection.start);
+        if word.start == word.end {
+            return;
+        }
+        word
+    } else {
+        let needle = doc.text[selection.start..selection.end].to_string();
+        let request = SearchRequest::new(needle, true, true, true);
+        let Some(found) = find_forward(&doc.text, &request, doc.selection.char_range.end) else {
+            return;
+        };
+        let found = ByteRange {
+            start: char_to_byte(&doc.text, found.start),
+            end: char_to_byte(&doc.text, found.end),
+        };
+        if found == selection || doc.selection.extra.contains(&found) {
+            data.info_message = Some("Every occurrence is already selected.".to_string());
+            return;
+        }
+        found
+    };
+    let doc = data.doc_mut();
+    let mut extra = doc.selection.extra.to_vec();
+    if selection.start != selection.end {
+        extra.push(selection);
+    }
+    doc.selection = SelectionState {
+        extra: Arc::new(extra),
+        ..SelectionState::from_bytes(&doc.text, target)
+    };
+    ctx.submit_command(APPLY_SELECTION.with(target).to(EDITOR_ID));
+}
+
+/// Applies a line command to the lines the selection touches, as one undo
+/// step.
+pub fn line_operation(ctx: &mut EventCtx, data: &mut AppState, operation: LineOperation) {
+    let doc = data.doc();
+    let selection = doc.selection.byte_range;
+    let Some(edit) = lines::apply(&doc.text, selection.start..selection.end, operation) else {
+        return;
+    };
+    apply_edit(ctx, data, edit);
+}
+
+fn apply_edit(ctx: &mut EventCtx, data: &mut AppState, edit: lines::Edit) {
+    let selection = ByteRange {
+        start: edit.selection.start,
+        end: edit.selection.end,
+    };
+    let range = ByteRange {
+        start: edit.range.start,
+        end: edit.range.end,
+    };
+    replace_and_select(ctx, data, range, &edit.replacement, selection);
+}
+
+/// Sorts the selected lines, or the document, by the field the sort panel
+/// names.
+pub fn sort_by_column(ctx: &mut EventCtx, data: &mut AppState, descending: bool) {
+    let doc = data.doc();
+    let column = match doc.search.sort_column.trim().parse::<usize>() {
+        Ok(column) if column > 0 => column,
+        _ => {
+            data.info_message = Some("Enter a column number, starting at 1.".to_string());
+            return;
+        }
+    };
+    let selection = doc.selection.byte_range;
+    let Some(edit) = lines::sort_by_column(
+        &doc.text,
+        selection.start..selection.end,
+        &doc.search.sort_delimiter,
+        column,
+        descending,
+    ) else {
+        return;
+    };
+    apply_edit(ctx, data, edit);
+}
+
+/// Replaces `range` as one undo step and selects the replacement.
+pub fn replace_range(ctx: &mut EventCtx, data: &mut AppState, range: ByteRange, value: &str) {
+    let selection = ByteRange {
+        start: range.start,
+        end: range.start + value.len(),
+    };
+    replace_and_select(ctx, data, range, value, selection);
+}
+
+/// Replaces `range` with `value`, then selects `selection` in the new text.
+fn replace_and_select(
+    ctx: &mut EventCtx,
+    data: &mut AppState,
+    range: ByteRange,
+    value: &str,
+    selection: ByteRange,
+) {
+    let doc = data.doc_mut();
+    if doc.text[range.start..range.end] == *value {
+        return;
+    }
+    doc.checkpoint();
+    doc.text.replace_range(range.start..range.end, value);
+    doc.selection = SelectionState::from_bytes(&doc.text, selection);
+    ctx.submit_command(APPLY_SELECTION.with(selection).to(EDITOR_ID));
+}
+
 fn insert_text(ctx: &mut EventCtx, data: &mut AppState, value: &str) {
+    let doc = data.doc_mut();
+    doc.checkpoint();
     let mut new_text = String::new();
-    let start = char_to_byte(&data.text, data.selection.char_range.start);
-    let end = char_to_byte(&data.text, data.selection.char_range.end);
-    new_text.push_str(&data.text[..start]);
+    let start = char_to_byte(&doc.text, doc.selection.char_range.start);
+    let end = char_to_byte(&doc.text, doc.selection.char_range.end);
+    new_text.push_str(&doc.text[..start]);
     new_text.push_str(value);
-    new_text.push_str(&data.text[end..]);
-    data.text = new_text;
+    new_text.push_str(&doc.text[end..]);
+    doc.text = new_text;
     let byte = start + value.len();
-    data.selection = SelectionState {
+    doc.selection = SelectionState {
         char_range: CharRange {
-            start: data.selection.char_range.start + value.chars().count(),
-            end: data.selection.char_range.start + value.chars().count(),
+            start: doc.selection.char_range.start + value.chars().count(),
+            end: doc.selection.char_range.start + value.chars().count(),
         },
         byte_range: ByteRange {
             start: byte,
             end: byte,
         },
+        extra: Default::default(),
     };
     ctx.submit_command(
         APPLY_SELECTION
@@ -1,27 +1,25 @@
 use std::fs;
-use std::sync::Arc;
+use std::path::Path;
 
 use druid::commands::SHOW_SAVE_PANEL;
 use druid::{EventCtx, FileDialogOptions, FileInfo, FileSpec};
 
-use crate::app::state::AppState;
+use crate::app::document::{Document, absolute_path};
+use crate::app::state::{AppState, Prompt, PromptChoice, PromptKind};
+use crate::cli::{FileArg, LaunchOptions};
+use crate::editor::{ByteRange, SelectionState};
+
+use super::text_utils::line_column_to_byte;
+use super::{recent_ops, recovery_ops, watch_ops};
 
 pub fn new_file(data: &mut AppState) {
-    data.text.clear();
-    data.file_path = None;
-    data.mark_saved();
+    data.open_document(Document::new());
 }
 
 pub fn save(ctx: &mut EventCtx, data: &mut AppState, save_as: bool) {
-    if !save_as {
-        if let Some(path) = data.pathbuf() {
-            if let Err(err) = fs::write(&path, &data.text) {
-                data.info_message = Some(format!("Unable to save file: {err}"));
-            } else {
-                data.mark_saved();
-            }
-            return;
-        }
+    if !save_as && data.doc().file_path.is_some() {
+        save_in_place(data);
+        return;
     }
     let options = FileDialogOptions::new()
         .allowed_types(vec![FileSpec::new("Text", &["txt", "md", "rs", ""])])
@@ -34,22 +32,177 @@ pub fn save(ctx: &mut EventCtx, data: &mut AppState, save_as: bool) {
 
 pub fn handle_save_selection(data: &mut AppState, info: &FileInfo) {
     let path = info.path().to_owned();
-    if let Err(err) = fs::write(&path, &data.text) {
+    // A close waits on this dialog only; a failed save keeps the tab open.
+    let close = data.pending_close.take() == Some(data.doc().id);
+    let doc = data.doc_mut();
+    if let Err(err) = write_file(&path, &doc.text) {
         data.info_message = Some(format!("Unable to save file: {err}"));
-    } else {
-        data.file_path = Some(Arc::new(path.to_string_lossy().to_string()));
-        data.mark_saved();
+        return;
+    }
+    doc.set_path(&path);
+    doc.mark_saved();
+    watch_ops::record_stamp(doc);
+    recovery_ops::discard_snapshot(doc);
+    recent_ops::remember(data, &path);
+    if close {
+        data.close_document(data.active);
     }
 }
 
 pub fn handle_open_selection(data: &mut AppState, info: &FileInfo) {
-    let path = info.path().to_owned();
-    match fs::read_to_string(&path) {
-        Ok(contents) => {
-            data.text = contents;
-            data.file_path = Some(Arc::new(path.to_string_lossy().to_string()));
-            data.mark_saved();
-        }
+    match open_path(data, info.path()) {
+        Ok(()) => recent_ops::remember(data, info.path()),
+        Err(err) => data.info_message = Some(format!("Unable to open file: {err}")),
+    }
+}
+
+pub fn open_recent(data: &mut AppState, path: &str) {
+    let path = Path::new(path);
+    if !path.exists() {
+        recent_ops::forget(data, path);
+        data.info_message = Some(format!(
+            "{} no longer exists and was removed from Recent Files.",
+            path.display()
+        ));
+        return;
+    }
+    match open_path(data, path) {
+        Ok(()) => recent_ops::remember(data, path),
         Err(err) => data.info_message = Some(format!("Unable to open file: {err}")),
     }
 }
+
+/// Loads whatever was named on the command line once the window exists.
+pub fn open_launch_files(data: &mut AppState, launch: LaunchOptions) {
+    for file in &launch.files {
+        open_file_arg(data, file, launch.create_missing);
+    }
+    if let Some(text) = launch.stdin_text {
+        let mut document = Document::new();
+        document.text = text;
+        data.open_document(document);
+    }
+}
+
+/// Closes the active tab, asking first when it has unsaved changes.
+pub fn close_active(data: &mut AppState) {
+    let doc = data.doc();
+    if doc.is_dirty() {
+        data.prompt = Some(Prompt {
+            kind: PromptKind::CloseDirty,
+            document: Some(doc.id),
+            message: format!("Save changes to {} before closing?", doc.display_name()),
+            detail: None,
+        });
+        return;
+    }
+    let closed = data.close_document(data.active);
+    recovery_ops::discard_snapshot(&closed);
+}
+
+pub fn respond_close(ctx: &mut EventCtx, data: &mut AppState, choice: PromptChoice) {
+    let prompt = data.prompt.take();
+    let Some(index) = prompt
+        .and_then(|prompt| prompt.document)
+        .and_then(|id| data.index_of(id))
+    else {
+        return;
+    };
+    data.active = index;
+    match choice {
+        PromptChoice::Save if data.doc().file_path.is_none() => {
+            data.pending_close = Some(data.doc().id);
+            save(ctx, data, true);
+        }
+        PromptChoice::Save => {
+            let saved = save_in_place(data);
+            if saved {
+                data.close_document(index);
+            }
+        }
+        PromptChoice::DontSave => {
+            let closed = data.close_document(index);
+            recovery_ops::discard_snapshot(&closed);
+        }
+        _ => {}
+    }
+}
+
+fn save_in_place(data: &mut AppState) -> bool {
+    let Some(path) = data.doc().pathbuf() else {
+        return false;
+    };
+    if !watch_ops::confirm_save(data) {
+        return false;
+    }
+    let doc = data.doc_mut();
+    if let Err(err) = write_file(&path, &doc.text) {
+        data.info_message = Some(format!("Unable to save file: {err}"));
+        return false;
+    }
+    doc.mark_saved();
+    watch_ops::record_stamp(doc);
+    recovery_ops::discard_snapshot(doc);
+    true
+}
+
+fn open_file_arg(data: &mut AppState, file: &FileArg, create_missing: bool) {
+    if !file.path.exists() {
+        if create_missing {
+            let mut document = Document::new();
+            document.set_path(&file.path);
+            data.open_document(document);
+            data.info_message = Some(format!(
+                "{} will be created when you save.",
+                file.path.display()
+            ));
+        } else {
+            data.info_message = Some(format!(
+                "{} does not exist. Pass --new-file to create it.",
+                file.path.display()
+            ));
+        }
+        return;
+    }
+    if let Err(err) = open_path(data, &file.path) {
+        data.info_message = Some(format!("Unable to open file: {err}"));
+        return;
+    }
+    if let Some(line) = file.line {
+        let doc = data.doc_mut();
+        let byte = line_column_to_byte(&doc.text, line, file.column.unwrap_or(1));
+        doc.selection = SelectionState::from_bytes(
+            &doc.text,
+            ByteRange {
+                start: byte,
+                end: byte,
+            },
+        );
+    }
+}
+
+/// Opens `path` in a new tab, or switches to it when it is already open.
+fn open_path(data: &mut AppState, path: &Path) -> std::io::Result<()> {
+    let key = absolute_path(path);
+    if let Some(index) = data.find_path(&key.to_string_lossy()) {
+        data.active = index;
+        return Ok(());
+    }
+    let contents = fs::read_to_string(path)?;
+    let mut document = Document::with_file(path, contents);
+    watch_ops::record_stamp(&mut document);
+    data.open_document(document);
+    Ok(())
+}
+
+/// Writes `text` to `path`, creating missing parent directories so files
+/// named on the command line with `--new-file` can be saved anywhere.
+fn write_file(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)
+}
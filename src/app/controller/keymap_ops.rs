@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use druid::{EventCtx, KeyEvent};
+
+use crate::app::actions;
+use crate::app::state::AppState;
+use crate::keymap::{self, Chord, KeyMatch, Keymap};
+
+/// Rebuilds the key bindings from the defaults and the user's keymap file.
+pub fn load(data: &mut AppState) {
+    let overrides = match keymap::load_overrides() {
+        Ok(overrides) => overrides,
+        Err(err) => {
+            data.info_message = Some(format!(
+                "Unable to load key bindings, using the defaults: {err}"
+            ));
+            return;
+        }
+    };
+    let (keymap, problems) = Keymap::resolve(&actions::default_bindings(), &overrides);
+    data.keymap = Arc::new(keymap);
+    if !problems.is_empty() {
+        data.info_message = Some(format!("Key bindings: {}", problems.join("; ")));
+    }
+}
+
+/// Feeds a key press through the keymap; returns `true` when it was consumed,
+/// either by running a command or as the first half of a two-step chord.
+pub fn handle_key(
+    ctx: &mut EventCtx,
+    data: &mut AppState,
+    pending: &mut Vec<Chord>,
+    event: &KeyEvent,
+) -> bool {
+    let chord = Chord::from_event(event);
+    if chord.is_modifier_only() {
+        return false;
+    }
+    pending.push(chord);
+    match data.keymap.lookup(pending) {
+        KeyMatch::Pending => {
+            data.info_message = Some(format!(
+                "{} was pressed. Waiting for the next key...",
+                pending[0]
+            ));
+            true
+        }
+        KeyMatch::Command(id) => {
+            let was_chord = pending.len() > 1;
+            pending.clear();
+            if was_chord {
+                data.info_message = None;
+            }
+            if let Some(action) = actions::find(id) {
+                ctx.submit_command(action.command());
+            }
+            true
+        }
+        KeyMatch::None => {
+            // An unknown second chord ends the sequence without typing anything.
+            let was_chord = pending.len() > 1;
+            pending.clear();
+            if was_chord {
+                data.info_message = None;
+            }
+            was_chord
+        }
+    }
+}
@@ -1,20 +1,67 @@
-use druid::commands::{OPEN_FILE, SAVE_FILE_AS};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use druid::commands::{OPEN_FILE, SAVE_FILE_AS, SAVE_PANEL_CANCELLED};
 use druid::widget::Controller;
-use druid::{Env, Event, EventCtx, FileInfo, Widget, commands};
+use druid::{Env, Event, EventCtx, FileInfo, Target, TimerToken, Widget, commands};
 
 use crate::app::commands::{
-    CMD_DECREASE_FONT, CMD_EXIT, CMD_FIND_NEXT, CMD_FIND_PREV, CMD_GO_TO, CMD_INCREASE_FONT,
-    CMD_NEW_FILE, CMD_REPLACE_ALL, CMD_REPLACE_ONE, CMD_SAVE_AS, CMD_SAVE_FILE, CMD_SET_FONT,
-    CMD_SHOW_ABOUT, CMD_SHOW_SEARCH, CMD_TIME_DATE, CMD_TOGGLE_STATUS, CMD_TOGGLE_WRAP,
+    CMD_ADD_NEXT_OCCURRENCE, CMD_AUTOSAVE_FAILED, CMD_CLEAR_RECENT, CMD_CLOSE_TAB,
+    CMD_CONVERT_CASE, CMD_CONVERT_INDENTATION, CMD_DECREASE_FONT, CMD_EXIT, CMD_FIND_NEXT,
+    CMD_FIND_PREV, CMD_FONTS_LOADED, CMD_GO_TO, CMD_HARD_WRAP, CMD_INCREASE_FONT,
+    CMD_LINE_OPERATION, CMD_NEW_FILE, CMD_NEXT_TAB, CMD_OPEN_RECENT, CMD_PREV_TAB,
+    CMD_PROMPT_RESPONSE, CMD_REPLACE_ALL, CMD_REPLACE_ONE, CMD_SAVE_AS, CMD_SAVE_FILE,
+    CMD_SET_FONT, CMD_SET_TAB_WIDTH, CMD_SET_THEME, CMD_SHOW_ABOUT, CMD_SHOW_FONT_PICKER,
+    CMD_SHOW_PALETTE, CMD_SHOW_SEARCH, CMD_SORT_BY_COLUMN, CMD_TIME_DATE, CMD_TOGGLE_COMMENT,
+    CMD_TOGGLE_CURRENT_LINE, CMD_TOGGLE_INSERT_SPACES, CMD_TOGGLE_LINE_NUMBERS, CMD_TOGGLE_STATUS,
+    CMD_TOGGLE_WHITESPACE, CMD_TOGGLE_WRAP, CMD_TOGGLE_WRAP_COLUMN, CMD_ZOOM_IN, CMD_ZOOM_OUT,
+    CMD_ZOOM_RESET,
 };
-use crate::app::state::AppState;
+use crate::app::state::{AppState, PromptKind};
+use crate::cli::LaunchOptions;
+use crate::fonts;
+use crate::keymap::Chord;
+
+use self::settings_ops::SettingsSync;
 
 mod edit_ops;
 mod file_ops;
+mod keymap_ops;
+mod recent_ops;
+mod recovery_ops;
 mod search_ops;
+mod session_ops;
+mod settings_ops;
 mod text_utils;
+mod theme_ops;
+mod watch_ops;
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
 
-pub struct AppController;
+pub struct AppController {
+    launch: Option<LaunchOptions>,
+    autosave_timer: TimerToken,
+    watch_timer: TimerToken,
+    /// Hash of the text last written to each recovery snapshot, keyed by snapshot id.
+    autosaved: HashMap<String, u64>,
+    settings: SettingsSync,
+    /// Chords typed so far towards a multi-step key binding.
+    pending_keys: Vec<Chord>,
+}
+
+impl AppController {
+    pub fn new(launch: LaunchOptions) -> Self {
+        Self {
+            launch: Some(launch),
+            autosave_timer: TimerToken::INVALID,
+            watch_timer: TimerToken::INVALID,
+            autosaved: HashMap::new(),
+            settings: SettingsSync::default(),
+            pending_keys: Vec::new(),
+        }
+    }
+}
 
 impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
     fn event(
@@ -26,10 +73,89 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
         env: &Env,
     ) {
         match event {
+            Event::WindowConnected => {
+                let sink = ctx.get_external_handle();
+                fonts::load_in_background(move || {
+                    let _ = sink.submit_command(CMD_FONTS_LOADED, (), Target::Auto);
+                });
+                theme_ops::load(data);
+                self.settings.load(data);
+                keymap_ops::load(data);
+                recent_ops::load(data);
+                session_ops::restore(data);
+                if let Some(launch) = self.launch.take() {
+                    file_ops::open_launch_files(data, launch);
+                }
+                recovery_ops::load_pending(data);
+                self.autosave_timer = ctx.request_timer(AUTOSAVE_INTERVAL);
+                self.watch_timer = ctx.request_timer(WATCH_INTERVAL);
+            }
+            Event::Timer(token) if *token == self.autosave_timer => {
+                recovery_ops::autosave(ctx, data, &mut self.autosaved);
+                self.autosave_timer = ctx.request_timer(AUTOSAVE_INTERVAL);
+                ctx.set_handled();
+            }
+            Event::Timer(token) if *token == self.watch_timer => {
+                watch_ops::check_disk(data);
+                self.settings.reload_if_changed(data);
+                self.watch_timer = ctx.request_timer(WATCH_INTERVAL);
+                ctx.set_handled();
+            }
+            Event::KeyDown(key)
+                if keymap_ops::handle_key(ctx, data, &mut self.pending_keys, key) =>
+            {
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_AUTOSAVE_FAILED) => {
+                if let Some(err) = cmd.get(CMD_AUTOSAVE_FAILED) {
+                    data.info_message = Some(format!("Autosave failed: {err}"));
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_FONTS_LOADED) => {
+                data.font_picker.loaded = true;
+                if let Some(problem) = settings_ops::missing_font(data) {
+                    data.info_message = Some(format!("Settings: {problem}"));
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_PROMPT_RESPONSE) => {
+                let kind = data.prompt.as_ref().map(|prompt| prompt.kind);
+                if let (Some(choice), Some(kind)) = (cmd.get(CMD_PROMPT_RESPONSE), kind) {
+                    match kind {
+                        PromptKind::Recovery => recovery_ops::respond(data, *choice),
+                        PromptKind::ExternalChange => watch_ops::respond(data, *choice),
+                        PromptKind::CloseDirty => file_ops::respond_close(ctx, data, *choice),
+                    }
+                }
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_NEW_FILE) => {
                 file_ops::new_file(data);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CMD_OPEN_RECENT) => {
+                if let Some(path) = cmd.get(CMD_OPEN_RECENT) {
+                    file_ops::open_recent(data, path);
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_CLEAR_RECENT) => {
+                recent_ops::clear(data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_CLOSE_TAB) => {
+                file_ops::close_active(data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_NEXT_TAB) => {
+                data.active = (data.active + 1) % data.documents.len();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_PREV_TAB) => {
+                data.active = (data.active + data.documents.len() - 1) % data.documents.len();
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_SAVE_FILE) => {
                 file_ops::save(ctx, data, false);
                 ctx.set_handled();
@@ -39,6 +165,7 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(CMD_EXIT) => {
+                session_ops::save(data);
                 ctx.submit_command(commands::QUIT_APP);
                 ctx.set_handled();
             }
@@ -50,25 +177,95 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
                 edit_ops::toggle_wrap(data);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CMD_TOGGLE_WRAP_COLUMN) => {
+                data.wrap_at_column = !data.wrap_at_column;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_HARD_WRAP) => {
+                edit_ops::hard_wrap_selection(ctx, data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_LINE_OPERATION) => {
+                edit_ops::line_operation(ctx, data, *cmd.get_unchecked(CMD_LINE_OPERATION));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_SORT_BY_COLUMN) => {
+                edit_ops::sort_by_column(ctx, data, *cmd.get_unchecked(CMD_SORT_BY_COLUMN));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_CONVERT_CASE) => {
+                edit_ops::convert_case(ctx, data, *cmd.get_unchecked(CMD_CONVERT_CASE));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_TOGGLE_COMMENT) => {
+                edit_ops::toggle_comment(ctx, data, *cmd.get_unchecked(CMD_TOGGLE_COMMENT));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_TOGGLE_INSERT_SPACES) => {
+                edit_ops::toggle_insert_spaces(data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_SET_TAB_WIDTH) => {
+                edit_ops::set_tab_width(data, *cmd.get_unchecked(CMD_SET_TAB_WIDTH));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_CONVERT_INDENTATION) => {
+                edit_ops::convert_indentation(
+                    ctx,
+                    data,
+                    *cmd.get_unchecked(CMD_CONVERT_INDENTATION),
+                );
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_ADD_NEXT_OCCURRENCE) => {
+                edit_ops::add_next_occurrence(ctx, data);
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_TOGGLE_STATUS) => {
                 edit_ops::toggle_status_bar(data);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CMD_TOGGLE_LINE_NUMBERS) => {
+                data.show_line_numbers = !data.show_line_numbers;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_TOGGLE_CURRENT_LINE) => {
+                data.highlight_current_line = !data.highlight_current_line;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_TOGGLE_WHITESPACE) => {
+                data.show_whitespace = !data.show_whitespace;
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_SHOW_ABOUT) => {
                 edit_ops::show_about(data);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CMD_SHOW_PALETTE) => {
+                data.palette.visible = true;
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_SHOW_SEARCH) => {
                 let mode = cmd.get(CMD_SHOW_SEARCH).copied();
                 search_ops::show_search(data, mode);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CMD_SHOW_FONT_PICKER) => {
+                edit_ops::show_font_picker(data);
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_SET_FONT) => {
-                if let Some(choice) = cmd.get(CMD_SET_FONT) {
-                    if edit_ops::set_font_choice(data, *choice) {
-                        ctx.request_layout();
-                        ctx.request_paint();
-                    }
+                if let Some(font) = cmd.get(CMD_SET_FONT)
+                    && edit_ops::set_font(data, font)
+                {
+                    ctx.request_layout();
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_SET_THEME) => {
+                if let Some(name) = cmd.get(CMD_SET_THEME) {
+                    theme_ops::set_theme(data, name);
                 }
                 ctx.set_handled();
             }
@@ -86,6 +283,32 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
                 }
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CMD_ZOOM_IN) => {
+                if edit_ops::zoom(data, 1) {
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_ZOOM_OUT) => {
+                if edit_ops::zoom(data, -1) {
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CMD_ZOOM_RESET) => {
+                if edit_ops::set_zoom(data, 100) {
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+            }
+            Event::Wheel(wheel) if wheel.mods.ctrl() => {
+                // Wheel up (negative delta) zooms in, one step per notch.
+                let steps = if wheel.wheel_delta.y < 0.0 { 1 } else { -1 };
+                if wheel.wheel_delta.y != 0.0 && edit_ops::zoom(data, steps) {
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(CMD_FIND_NEXT) => {
                 search_ops::run_search(ctx, data, true);
                 ctx.set_handled();
@@ -118,10 +341,16 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
                 }
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(SAVE_PANEL_CANCELLED) => {
+                data.pending_close = None;
+                ctx.set_handled();
+            }
             Event::WindowCloseRequested => {
                 if data.is_dirty() {
                     data.info_message = Some("Unsaved changes will be lost.".to_string());
                     ctx.set_handled();
+                } else {
+                    session_ops::save(data);
                 }
             }
             Event::MouseDown(_) => {
@@ -131,5 +360,6 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AppController {
         }
 
         child.event(ctx, event, data, env);
+        self.settings.save_if_changed(data);
     }
 }
@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::app::state::AppState;
+use crate::recent;
+
+pub fn load(data: &mut AppState) {
+    match recent::load() {
+        Ok(files) => set_files(data, &files),
+        Err(err) => data.info_message = Some(format!("Unable to read recent files: {err}")),
+    }
+}
+
+pub fn remember(data: &mut AppState, path: &Path) {
+    let mut files = files(data);
+    recent::promote(&mut files, path);
+    set_files(data, &files);
+    persist(data, &files);
+}
+
+pub fn forget(data: &mut AppState, path: &Path) {
+    let mut files = files(data);
+    files.retain(|file| file != path);
+    set_files(data, &files);
+    persist(data, &files);
+}
+
+pub fn clear(data: &mut AppState) {
+    set_files(data, &[]);
+    persist(data, &[]);
+}
+
+fn files(data: &AppState) -> Vec<PathBuf> {
+    data.recent_files
+        .iter()
+        .map(|file| PathBuf::from(file.as_str()))
+        .collect()
+}
+
+fn set_files(data: &mut AppState, files: &[PathBuf]) {
+    data.recent_files = files
+        .iter()
+        .map(|file| Arc::new(file.to_string_lossy().to_string()))
+        .collect();
+}
+
+fn persist(data: &mut AppState, files: &[PathBuf]) {
+    if let Err(err) = recent::save(files) {
+        data.info_message = Some(format!("Unable to save recent files: {err}"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+
+use druid::{EventCtx, ExtEventSink, Target};
+
+use crate::app::commands::CMD_AUTOSAVE_FAILED;
+use crate::app::document::Document;
+use crate::app::state::{AppState, Prompt, PromptChoice, PromptKind};
+use crate::diff::render_diff;
+use crate::recovery::{self, RecoveredDocument};
+
+use super::watch_ops;
+
+pub fn load_pending(data: &mut AppState) {
+    match recovery::load_snapshots() {
+        Ok(documents) => data.pending_recovery = documents.into_iter().collect(),
+        Err(err) => data.info_message = Some(format!("Unable to read recovery files: {err}")),
+    }
+    offer_next(data);
+}
+
+pub fn respond(data: &mut AppState, choice: PromptChoice) {
+    if !matches!(choice, PromptChoice::Restore | PromptChoice::Discard) {
+        return;
+    }
+    let Some(document) = data.pending_recovery.pop_front() else {
+        data.prompt = None;
+        return;
+    };
+    if choice == PromptChoice::Restore {
+        restore(data, document);
+    } else if let Err(err) = recovery::remove_snapshot(&document.id) {
+        data.info_message = Some(format!("Unable to discard recovery file: {err}"));
+    }
+    offer_next(data);
+}
+
+/// Writes snapshots of dirty buffers that changed since their last snapshot,
+/// and drops snapshots of buffers that are clean again or were closed.
+pub fn autosave(ctx: &mut EventCtx, data: &AppState, written: &mut HashMap<String, u64>) {
+    written.retain(|id, _| {
+        let open = data
+            .documents
+            .iter()
+            .any(|doc| doc.is_dirty() && doc.recovery_id.as_str() == id);
+        if !open {
+            submit(Job::Remove(id.clone()));
+        }
+        open
+    });
+    for doc in data.documents.iter().filter(|doc| doc.is_dirty()) {
+        let id = doc.recovery_id.to_string();
+        let hash = text_hash(&doc.text);
+        if written.get(&id) == Some(&hash) {
+            continue;
+        }
+        written.insert(id.clone(), hash);
+
+        submit(Job::Write {
+            id,
+            path: doc.file_path.as_deref().cloned(),
+            text: doc.text.clone(),
+            sink: ctx.get_external_handle(),
+        });
+    }
+}
+
+pub fn discard_snapshot(doc: &Document) {
+    submit(Job::Remove(doc.recovery_id.to_string()));
+}
+
+enum Job {
+    Write {
+        id: String,
+        path: Option<String>,
+        text: String,
+        sink: ExtEventSink,
+    },
+    Remove(String),
+}
+
+/// Hands `job` to the one thread that touches snapshot files, so writes and
+/// removals for a buffer land in the order they were asked for.
+fn submit(job: Job) {
+    static WORKER: OnceLock<Sender<Job>> = OnceLock::new();
+    let worker = WORKER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        thread::spawn(move || {
+            for job in receiver {
+                match job {
+                    Job::Write {
+                        id,
+                        path,
+                        text,
+                        sink,
+                    } => {
+                        if let Err(err) = recovery::write_snapshot(&id, path.as_deref(), &text) {
+                            let _ = sink.submit_command(
+                                CMD_AUTOSAVE_FAILED,
+                                err.to_string(),
+                                Target::Auto,
+                            );
+                        }
+                    }
+                    Job::Remove(id) => {
+                        let _ = recovery::remove_snapshot(&id);
+                    }
+                }
+            }
+        });
+        sender
+    });
+    let _ = worker.send(job);
+}
+
+fn restore(data: &mut AppState, recovered: RecoveredDocument) {
+    let mut doc = Document::new();
+    doc.saved_snapshot = recovered
+        .original_path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path.as_str()).ok())
+        .unwrap_or_default();
+    doc.text = recovered.text.to_string();
+    doc.file_path = recovered.original_path.clone();
+    // Keep writing to the recovered file so the restore itself survives a
+    // crash; claiming it first keeps other instances from offering it.
+    doc.recovery_id = recovery::claim_snapshot(&recovered.id).unwrap_or(recovered.id);
+    watch_ops::record_stamp(&mut doc);
+    // Session restore may already have opened the file; take over its tab
+    // unless it has been edited since.
+    let open = recovered
+        .original_path
+        .as_ref()
+        .and_then(|path| data.find_path(path))
+        .filter(|&index| !data.documents[index].is_dirty());
+    match open {
+        Some(index) => {
+            let replaced = std::mem::replace(&mut data.documents[index], doc);
+            discard_snapshot(&replaced);
+            data.active = index;
+        }
+        None => data.open_document(doc),
+    }
+}
+
+fn offer_next(data: &mut AppState) {
+    let Some(document) = data.pending_recovery.front() else {
+        if data
+            .prompt
+            .as_ref()
+            .is_some_and(|prompt| prompt.kind == PromptKind::Recovery)
+        {
+            data.prompt = None;
+        }
+        return;
+    };
+    let name = document
+        .original_path
+        .as_ref()
+        .map(|path| path.to_string())
+        .unwrap_or_else(|| "an untitled document".to_string());
+    let on_disk = document
+        .original_path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path.as_str()).ok());
+    let detail = match &on_disk {
+        Some(disk) => render_diff(disk, &document.text)
+            .map(|diff| format!("Changes compared to the file on disk:\n{diff}")),
+        None => Some(preview(&document.text)),
+    };
+    data.prompt = Some(Prompt {
+        kind: PromptKind::Recovery,
+        document: None,
+        message: format!(
+            "RustPad recovered unsaved changes to {name} from {}.",
+            document.saved_at
+        ),
+        detail: detail.map(Arc::new),
+    });
+}
+
+fn preview(text: &str) -> String {
+    const PREVIEW_LINES: usize = 40;
+    let mut out: String = text
+        .lines()
+        .take(PREVIEW_LINES)
+        .map(|line| format!("{line}\n"))
+        .collect();
+    if text.lines().nth(PREVIEW_LINES).is_some() {
+        out.push_str("...\n");
+    }
+    out
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
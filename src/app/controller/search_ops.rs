@@ -15,7 +15,7 @@ pub fn show_search(data: &mut AppState, mode: Option<SearchMode>) {
 }
 
 pub fn run_search(ctx: &mut EventCtx, data: &mut AppState, forward: bool) {
-    let request = panel_request(data).or_else(|| data.last_search.clone());
+    let request = panel_request(data).or_else(|| data.doc().last_search.clone());
     if request.is_none() {
         data.search_mode = SearchMode::Find;
         data.search_visible = true;
@@ -23,18 +23,19 @@ pub fn run_search(ctx: &mut EventCtx, data: &mut AppState, forward: bool) {
     }
     let mut request = request.unwrap();
     request.search_down = forward;
+    let doc = data.doc();
     let start = if forward {
-        data.selection.char_range.end
+        doc.selection.char_range.end
     } else {
-        data.selection.char_range.start
+        doc.selection.char_range.start
     };
     let result = if forward {
-        find_forward(&data.text, &request, start)
+        find_forward(&doc.text, &request, start)
     } else {
-        find_backward(&data.text, &request, start)
+        find_backward(&doc.text, &request, start)
     };
     if let Some(range) = result {
-        data.last_search = Some(request);
+        data.doc_mut().last_search = Some(request);
         highlight_range(ctx, data, range);
     } else {
         data.info_message = Some(format!("Cannot find \"{}\"", request.needle));
@@ -49,7 +50,8 @@ pub fn replace_once(ctx: &mut EventCtx, data: &mut AppState) {
             return;
         }
     };
-    let selected = &data.text[data.selection.byte_range.start..data.selection.byte_range.end];
+    let doc = data.doc_mut();
+    let selected = &doc.text[doc.selection.byte_range.start..doc.selection.byte_range.end];
     let matches = if request.match_case {
         selected == request.needle
     } else {
@@ -59,27 +61,25 @@ pub fn replace_once(ctx: &mut EventCtx, data: &mut AppState) {
         run_search(ctx, data, true);
         return;
     }
-    let start_byte = data.selection.byte_range.start;
-    let end_byte = data.selection.byte_range.end;
-    data.text
-        .replace_range(start_byte..end_byte, &data.search.replacement);
-    let inserted_chars = data.search.replacement.chars().count();
-    let start_char = data.selection.char_range.start;
-    data.selection = SelectionState {
+    doc.checkpoint();
+    let start_byte = doc.selection.byte_range.start;
+    let end_byte = doc.selection.byte_range.end;
+    doc.text
+        .replace_range(start_byte..end_byte, &doc.search.replacement);
+    let inserted_chars = doc.search.replacement.chars().count();
+    let start_char = doc.selection.char_range.start;
+    doc.selection = SelectionState {
         char_range: CharRange {
             start: start_char + inserted_chars,
             end: start_char + inserted_chars,
         },
         byte_range: ByteRange {
-            start: start_byte + data.search.replacement.len(),
-            end: start_byte + data.search.replacement.len(),
+            start: start_byte + doc.search.replacement.len(),
+            end: start_byte + doc.search.replacement.len(),
         },
+        extra: Default::default(),
     };
-    ctx.submit_command(
-        APPLY_SELECTION
-            .with(data.selection.byte_range)
-            .to(EDITOR_ID),
-    );
+    ctx.submit_command(APPLY_SELECTION.with(doc.selection.byte_range).to(EDITOR_ID));
 }
 
 pub fn replace_all(data: &mut AppState) {
@@ -92,22 +92,26 @@ pub fn replace_all(data: &mut AppState) {
     };
     request.wrap = false;
     request.search_down = true;
-    let mut text = data.text.clone();
+    let doc = data.doc_mut();
+    let mut text = doc.text.clone();
     let mut cursor = 0;
     let mut replaced = 0;
     while let Some(range) = find_forward(&text, &request, cursor) {
         let start_byte = char_to_byte(&text, range.start);
         let end_byte = char_to_byte(&text, range.end);
-        text.replace_range(start_byte..end_byte, &data.search.replacement);
-        cursor = range.start + data.search.replacement.chars().count();
+        text.replace_range(start_byte..end_byte, &doc.search.replacement);
+        cursor = range.start + doc.search.replacement.chars().count();
         replaced += 1;
     }
-    data.text = text;
+    if replaced > 0 {
+        doc.checkpoint();
+        doc.text = text;
+    }
     data.info_message = Some(format!("Replaced {replaced} occurrence(s)."));
 }
 
 pub fn goto_line(ctx: &mut EventCtx, data: &mut AppState) {
-    let input = data.search.goto_line.trim();
+    let input = data.doc().search.goto_line.trim();
     if input.is_empty() {
         data.info_message = Some("Enter a line number.".to_string());
         return;
@@ -116,7 +120,7 @@ pub fn goto_line(ctx: &mut EventCtx, data: &mut AppState) {
         Ok(target) if target > 0 => {
             let mut current = 1usize;
             let mut char_index = 0usize;
-            for ch in data.text.chars() {
+            for ch in data.doc().text.chars() {
                 if current == target {
                     break;
                 }
@@ -129,7 +133,7 @@ pub fn goto_line(ctx: &mut EventCtx, data: &mut AppState) {
                 data.info_message = Some("Line not found.".to_string());
                 return;
             }
-            let byte = char_to_byte(&data.text, char_index);
+            let byte = char_to_byte(&data.doc().text, char_index);
             ctx.submit_command(
                 APPLY_SELECTION
                     .with(ByteRange {
@@ -146,22 +150,23 @@ pub fn goto_line(ctx: &mut EventCtx, data: &mut AppState) {
 }
 
 fn panel_request(data: &AppState) -> Option<SearchRequest> {
-    let needle = data.search.query.trim();
+    let needle = data.doc().search.query.trim();
+    let options = &data.search_options;
     if needle.is_empty() {
         None
     } else {
         Some(SearchRequest::new(
             needle.to_string(),
-            data.search.match_case,
-            data.search.search_down,
-            data.search.wrap,
+            options.match_case,
+            options.search_down,
+            options.wrap,
         ))
     }
 }
 
 fn highlight_range(ctx: &mut EventCtx, data: &mut AppState, range: CharRange) {
-    let start_byte = char_to_byte(&data.text, range.start);
-    let end_byte = char_to_byte(&data.text, range.end);
+    let start_byte = char_to_byte(&data.doc().text, range.start);
+    let end_byte = char_to_byte(&data.doc().text, range.end);
     ctx.submit_command(
         APPLY_SELECTION
             .with(ByteRange {
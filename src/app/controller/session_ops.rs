@@ -0,0 +1,87 @@
+use std::fs;
+
+use druid::kurbo::Vec2;
+
+use crate::app::document::Document;
+use crate::app::state::AppState;
+use crate::editor::{ByteRange, SelectionState};
+use crate::session::{self, Session, SessionDocument};
+
+use super::watch_ops;
+
+/// Reopens the files saved by the previous run.
+pub fn restore(data: &mut AppState) {
+    let session = match session::load() {
+        Ok(Some(session)) => session,
+        Ok(None) => return,
+        Err(err) => {
+            data.info_message = Some(format!("Unable to restore last session: {err}"));
+            return;
+        }
+    };
+
+    let mut active_id = None;
+    let mut missing = Vec::new();
+    for (index, entry) in session.documents.iter().enumerate() {
+        let Ok(text) = fs::read_to_string(&entry.path) else {
+            missing.push(entry.path.display().to_string());
+            continue;
+        };
+        let mut doc = Document::with_file(&entry.path, text);
+        watch_ops::record_stamp(&mut doc);
+        doc.selection = SelectionState::from_bytes(
+            &doc.text,
+            ByteRange {
+                start: entry.selection_start,
+                end: entry.selection_end,
+            },
+        );
+        doc.scroll_offset = Vec2::new(entry.scroll_x, entry.scroll_y);
+        if index == session.active || active_id.is_none() {
+            active_id = Some(doc.id);
+        }
+        data.open_document(doc);
+    }
+    if let Some(index) = active_id.and_then(|id| data.index_of(id)) {
+        data.active = index;
+    }
+    if !missing.is_empty() {
+        data.info_message = Some(format!(
+            "Skipped {} file(s) from the last session that no longer exist: {}",
+            missing.len(),
+            missing.join(", ")
+        ));
+    }
+}
+
+pub fn save(data: &AppState) {
+    let saved: Vec<(usize, SessionDocument)> = data
+        .documents
+        .iter()
+        .enumerate()
+        .filter_map(|(index, doc)| {
+            let path = doc.pathbuf()?;
+            let range = doc.selection.byte_range;
+            Some((
+                index,
+                SessionDocument {
+                    path,
+                    selection_start: range.start,
+                    selection_end: range.end,
+                    scroll_x: doc.scroll_offset.x,
+                    scroll_y: doc.scroll_offset.y,
+                },
+            ))
+        })
+        .collect();
+    let active = saved
+        .iter()
+        .position(|(index, _)| *index == data.active)
+        .unwrap_or(0);
+    let session = Session {
+        active,
+        documents: saved.into_iter().map(|(_, doc)| doc).collect(),
+    };
+    // We are on the way out; there is nowhere left to report a failure.
+    let _ = session::save(&session);
+}
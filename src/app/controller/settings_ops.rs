@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use crate::app::state::{AppState, FontSettings};
+use crate::fonts;
+use crate::settings::{self, FontConfig, SearchConfig, Settings};
+use crate::watch::FileStamp;
+
+use super::theme_ops;
+
+/// Widest column a ruler or wrap column may be set to.
+const MAX_COLUMN: u16 = 1000;
+const MAX_TAB_WIDTH: u8 = 16;
+
+/// Keeps `settings.toml` and the app state in step in both directions.
+#[derive(Default)]
+pub struct SettingsSync {
+    stamp: Option<FileStamp>,
+    /// The settings last read or written, or `None` while the file on disk
+    /// is invalid and must not be overwritten.
+    synced: Option<Settings>,
+}
+
+impl SettingsSync {
+    pub fn load(&mut self, data: &mut AppState) {
+        self.stamp = settings::settings_path().and_then(|path| FileStamp::read(&path).ok());
+        match settings::load() {
+            Ok(loaded) => {
+                let problems = apply(data, &loaded.unwrap_or_default());
+                if !problems.is_empty() {
+                    data.info_message = Some(format!("Settings: {}", problems.join("; ")));
+                }
+                self.synced = Some(snapshot(data));
+            }
+            Err(err) => {
+                data.info_message = Some(format!("Unable to load settings: {err}"));
+                self.synced = None;
+            }
+        }
+    }
+
+    /// Re-applies the settings file when it has been edited outside RustPad.
+    pub fn reload_if_changed(&mut self, data: &mut AppState) {
+        let stamp = settings::settings_path().and_then(|path| FileStamp::read(&path).ok());
+        if stamp.is_some() && stamp != self.stamp {
+            self.load(data);
+        }
+    }
+
+    pub fn save_if_changed(&mut self, data: &mut AppState) {
+        let Some(synced) = &self.synced else {
+            return;
+        };
+        let current = snapshot(data);
+        if *synced == current {
+            return;
+        }
+        if let Err(err) = settings::save(&current) {
+            data.info_message = Some(format!("Unable to save settings: {err}"));
+        }
+        self.stamp = settings::settings_path().and_then(|path| FileStamp::read(&path).ok());
+        self.synced = Some(current);
+    }
+}
+
+/// Copies valid values into the state and describes any that were rejected.
+fn apply(data: &mut AppState, settings: &Settings) -> Vec<String> {
+    let mut problems = Vec::new();
+    data.word_wrap = settings.word_wrap;
+    data.show_status_bar = settings.show_status_bar;
+    data.show_line_numbers = settings.show_line_numbers;
+    data.highlight_current_line = settings.highlight_current_line;
+    data.show_whitespace = settings.show_whitespace;
+    let (rulers, rejected): (Vec<u16>, Vec<u16>) = settings
+        .rulers
+        .iter()
+        .partition(|column| (1..=MAX_COLUMN).contains(*column));
+    if !rejected.is_empty() {
+        problems.push(format!("rulers must be between 1 and {MAX_COLUMN}"));
+    }
+    if data.rulers.as_slice() != rulers.as_slice() {
+        data.rulers = Arc::new(rulers);
+    }
+    data.wrap_at_column = settings.wrap_at_column;
+    if (1..=MAX_COLUMN).contains(&settings.wrap_column) {
+        data.wrap_column = settings.wrap_column;
+    } else {
+        problems.push(format!(
+            "wrap column {} is outside 1-{MAX_COLUMN}",
+            settings.wrap_column
+        ));
+    }
+    if (1..=MAX_TAB_WIDTH).contains(&settings.tab_width) {
+        data.tab_width = settings.tab_width;
+    } else {
+        problems.push(format!(
+            "tab width {} is outside 1-{MAX_TAB_WIDTH}",
+            settings.tab_width
+        ));
+    }
+    data.insert_spaces = settings.insert_spaces;
+    if !theme_ops::set_theme(data, &settings.theme) {
+        problems.push(format!("unknown theme \"{}\"", settings.theme));
+    }
+    // A missing family is kept so the file isn't rewritten; only the display
+    // falls back.
+    data.font.family = settings.font.family.clone();
+    if let Some(problem) = missing_font(data) {
+        problems.push(problem);
+    }
+    if (1..=1000).contains(&settings.font.weight) {
+        data.font.weight = settings.font.weight;
+    } else {
+        problems.push(format!(
+            "font weight {} is outside 1-1000",
+            settings.font.weight
+        ));
+    }
+    data.font.italic = settings.font.italic;
+    if (AppState::MIN_ZOOM..=AppState::MAX_ZOOM).contains(&settings.zoom_percent) {
+        data.zoom_percent = settings.zoom_percent;
+    } else {
+        problems.push(format!(
+            "zoom {}% is outside {}-{}%",
+            settings.zoom_percent,
+            AppState::MIN_ZOOM,
+            AppState::MAX_ZOOM
+        ));
+    }
+    if (FontSettings::MIN_SIZE..=FontSettings::MAX_SIZE).contains(&settings.font.size) {
+        data.font.size = settings.font.size;
+    } else {
+        problems.push(format!(
+            "font size {} is outside {}-{}",
+            settings.font.size,
+            FontSettings::MIN_SIZE,
+            FontSettings::MAX_SIZE
+        ));
+    }
+    data.search_options.match_case = settings.search.match_case;
+    data.search_options.search_down = settings.search.search_down;
+    data.search_options.wrap = settings.search.wrap_around;
+    problems
+}
+
+/// Describes the configured font family when it is known not to be
+/// installed.
+pub fn missing_font(data: &AppState) -> Option<String> {
+    (!fonts::is_installed(&data.font.family)).then(|| {
+        format!(
+            "font family \"{}\" is not installed, using {}",
+            data.font.family,
+            fonts::FALLBACK_FAMILY
+        )
+    })
+}
+
+fn snapshot(data: &AppState) -> Settings {
+    Settings {
+        word_wrap: data.word_wrap,
+        show_status_bar: data.show_status_bar,
+        show_line_numbers: data.show_line_numbers,
+        highlight_current_line: data.highlight_current_line,
+        show_whitespace: data.show_whitespace,
+        rulers: data.rulers.to_vec(),
+        wrap_at_column: data.wrap_at_column,
+        wrap_column: data.wrap_column,
+        tab_width: data.tab_width,
+        insert_spaces: data.insert_spaces,
+        theme: data.theme.name.clone(),
+        zoom_percent: data.zoom_percent,
+        font: FontConfig {
+            family: data.font.family.clone(),
+            size: data.font.size,
+            weight: data.font.weight,
+            italic: data.font.italic,
+        },
+        search: SearchConfig {
+            match_case: data.search_options.match_case,
+            search_down: data.search_options.search_down,
+            wrap_around: data.search_options.wrap,
+        },
+    }
+}
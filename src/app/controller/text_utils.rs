@@ -1,3 +1,5 @@
+use crate::editor::ByteRange;
+
 pub fn char_to_byte(text: &str, char_index: usize) -> usize {
     if char_index == 0 {
         return 0;
@@ -11,3 +13,53 @@ pub fn char_to_byte(text: &str, char_index: usize) -> usize {
     }
     text.len()
 }
+
+/// Byte offset of a 1-based line and column, clamped to the end of the line
+/// (or of the text when the line is past the end).
+pub fn line_column_to_byte(text: &str, line: usize, column: usize) -> usize {
+    let mut line_start = 0;
+    for _ in 1..line.max(1) {
+        match text[line_start..].find('\n') {
+            Some(offset) => line_start += offset + 1,
+            None => return text.len(),
+        }
+    }
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |offset| line_start + offset);
+    text[line_start..line_end]
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(line_end, |(offset, _)| line_start + offset)
+}
+
+/// Grows `range` to cover whole lines, including the last line's newline.
+/// A selection ending at the very start of a line does not take that line.
+pub fn full_lines(text: &str, range: ByteRange) -> ByteRange {
+    let end = if range.end > range.start && text[..range.end].ends_with('\n') {
+        range.end - 1
+    } else {
+        range.end
+    };
+    let start = text[..range.start].rfind('\n').map_or(0, |index| index + 1);
+    let end = text[end..]
+        .find('\n')
+        .map_or(text.len(), |index| end + index + 1);
+    ByteRange { start, end }
+}
+
+/// The word (letters, digits and underscores) touching `offset`; empty when
+/// there is none.
+pub fn word_at(text: &str, offset: usize) -> ByteRange {
+    let is_word = |ch: char| ch.is_alphanumeric() || ch == '_';
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, ch)| is_word(ch))
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = text[offset..]
+        .find(|ch: char| !is_word(ch))
+        .map_or(text.len(), |index| offset + index);
+    ByteRange { start, end }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::app::state::AppState;
+use crate::theme::{self, Theme};
+
+/// Collects the built-in themes and the user's theme files.
+pub fn load(data: &mut AppState) {
+    let (user_themes, problems) = theme::load_user_themes();
+    let mut themes = Theme::built_in();
+    for user_theme in user_themes {
+        // A user file may redefine a built-in theme by reusing its name.
+        match themes
+            .iter_mut()
+            .find(|known| known.name == user_theme.name)
+        {
+            Some(known) => *known = user_theme,
+            None => themes.push(user_theme),
+        }
+    }
+    data.themes = Arc::new(themes);
+    if !problems.is_empty() {
+        data.info_message = Some(format!("Themes: {}", problems.join("; ")));
+    }
+}
+
+/// Switches to the theme called `name`; `false` when there is no such theme.
+pub fn set_theme(data: &mut AppState, name: &str) -> bool {
+    match data.themes.iter().find(|theme| theme.name == name) {
+        Some(theme) => {
+            data.theme = theme.clone();
+            true
+        }
+        None => false,
+    }
+}
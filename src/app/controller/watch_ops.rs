@@ -0,0 +1,136 @@
+use std::fs;
+use std::sync::Arc;
+
+use crate::app::document::Document;
+use crate::app::state::{AppState, Prompt, PromptChoice, PromptKind};
+use crate::diff::render_diff;
+use crate::watch::{self, DiskChange, FileStamp};
+
+/// Polls every open file for changes made by other programs.
+pub fn check_disk(data: &mut AppState) {
+    for index in 0..data.documents.len() {
+        // Don't stack a second question on top of one the user hasn't answered.
+        if data.prompt.is_some() {
+            return;
+        }
+        let doc = &mut data.documents[index];
+        let Some(path) = doc.pathbuf() else {
+            continue;
+        };
+        match watch::check(&path, doc.disk_stamp) {
+            DiskChange::Unchanged => {}
+            DiskChange::Modified if !doc.is_dirty() => match reload(doc) {
+                Ok(()) => {
+                    data.info_message = Some(format!(
+                        "{} was changed on disk and has been reloaded.",
+                        doc.display_name()
+                    ))
+                }
+                Err(err) => data.info_message = Some(format!("Unable to reload file: {err}")),
+            },
+            // Asking about a background tab would pull the user away from the
+            // one they are typing in; the poll after they switch to it asks.
+            DiskChange::Modified if index != data.active => doc.disk_conflict = true,
+            DiskChange::Modified => {
+                doc.disk_conflict = false;
+                prompt_conflict(data);
+            }
+            DiskChange::Deleted => {
+                doc.disk_stamp = None;
+                data.info_message = Some(format!(
+                    "{} was deleted from disk. Save to recreate it.",
+                    doc.display_name()
+                ));
+            }
+            DiskChange::Renamed(new_path) => {
+                let old_name = doc.display_name();
+                doc.set_path(&new_path);
+                data.info_message =
+                    Some(format!("{old_name} was renamed to {}.", doc.display_name()));
+            }
+        }
+    }
+}
+
+/// Returns `false` (and asks the user what to do) when saving the active
+/// document would overwrite changes made on disk since it was loaded.
+pub fn confirm_save(data: &mut AppState) -> bool {
+    let doc = data.doc();
+    let Some(path) = doc.pathbuf() else {
+        return true;
+    };
+    match watch::check(&path, doc.disk_stamp) {
+        DiskChange::Modified => {
+            prompt_conflict(data);
+            false
+        }
+        _ => true,
+    }
+}
+
+pub fn respond(data: &mut AppState, choice: PromptChoice) {
+    let Some(index) = data
+        .prompt
+        .as_ref()
+        .and_then(|prompt| prompt.document)
+        .and_then(|id| data.index_of(id))
+    else {
+        data.prompt = None;
+        return;
+    };
+    let doc = &mut data.documents[index];
+    match choice {
+        PromptChoice::Reload => {
+            data.prompt = None;
+            if let Err(err) = reload(doc) {
+                data.info_message = Some(format!("Unable to reload file: {err}"));
+            }
+        }
+        PromptChoice::KeepMine => {
+            data.prompt = None;
+            // Accept the current disk state as seen, so the next save goes through.
+            record_stamp(doc);
+        }
+        PromptChoice::ShowDiff => {
+            let disk = doc
+                .pathbuf()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            let diff = render_diff(&disk, &doc.text)
+                .unwrap_or_else(|| "The file on disk matches the buffer.".to_string());
+            if let Some(prompt) = data.prompt.as_mut() {
+                prompt.detail = Some(Arc::new(format!("- on disk, + in RustPad\n{diff}")));
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn record_stamp(doc: &mut Document) {
+    doc.disk_stamp = doc.pathbuf().and_then(|path| FileStamp::read(&path).ok());
+}
+
+fn reload(doc: &mut Document) -> std::io::Result<()> {
+    let Some(path) = doc.pathbuf() else {
+        return Ok(());
+    };
+    let contents = fs::read_to_string(&path)?;
+    doc.checkpoint();
+    doc.text = contents;
+    doc.mark_saved();
+    record_stamp(doc);
+    Ok(())
+}
+
+fn prompt_conflict(data: &mut AppState) {
+    let doc = data.doc();
+    data.prompt = Some(Prompt {
+        kind: PromptKind::ExternalChange,
+        document: Some(doc.id),
+        message: format!(
+            "{} was changed on disk and has unsaved changes in RustPad.",
+            doc.display_name()
+        ),
+        detail: None,
+    });
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use druid::kurbo::Vec2;
+use druid::{Data, Lens};
+
+use crate::editor::{EditorMetrics, History, SelectionState};
+use crate::folding::Folds;
+use crate::indent::{self, IndentStyle};
+use crate::recovery;
+use crate::search::SearchRequest;
+use crate::watch::FileStamp;
+
+use super::state::SearchPanelState;
+
+/// One open buffer, shown as a tab.
+#[derive(Clone, Data, Lens)]
+pub struct Document {
+    /// Stable identity for the lifetime of the tab; indices shift on reorder.
+    pub id: usize,
+    pub text: String,
+    pub file_path: Option<Arc<String>>,
+    pub saved_snapshot: String,
+    pub disk_stamp: Option<FileStamp>,
+    /// The file changed on disk while this tab, with unsaved edits, was in
+    /// the background; the user is asked once it is shown.
+    pub disk_conflict: bool,
+    pub metrics: EditorMetrics,
+    pub selection: SelectionState,
+    pub scroll_offset: Vec2,
+    pub search: SearchPanelState,
+    pub last_search: Option<SearchRequest>,
+    pub history: History,
+    /// Indentation found in the file when it was opened; `None` follows the
+    /// settings.
+    #[data(eq)]
+    pub indent_style: Option<IndentStyle>,
+    pub folds: Folds,
+    pub recovery_id: Arc<String>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            text: String::new(),
+            file_path: None,
+            saved_snapshot: String::new(),
+            disk_stamp: None,
+            disk_conflict: false,
+            metrics: EditorMetrics::default(),
+            selection: SelectionState::default(),
+            scroll_offset: Vec2::ZERO,
+            search: SearchPanelState::default(),
+            last_search: None,
+            history: History::default(),
+            indent_style: None,
+            folds: Folds::default(),
+            recovery_id: recovery::new_snapshot_id(),
+        }
+    }
+
+    pub fn with_file(path: &Path, text: String) -> Self {
+        let mut document = Self::new();
+        document.indent_style = indent::detect(&text);
+        document.text = text;
+        document.set_path(path);
+        document.mark_saved();
+        document
+    }
+
+    pub fn display_name(&self) -> String {
+        self.file_path
+            .as_ref()
+            .and_then(|arc| {
+                PathBuf::from(arc.as_str())
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| "Untitled".to_string())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.text != self.saved_snapshot
+    }
+
+    /// An untitled, empty, unmodified buffer that opening a file may replace.
+    pub fn is_pristine(&self) -> bool {
+        self.file_path.is_none() && self.text.is_empty() && !self.is_dirty()
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_snapshot = self.text.clone();
+    }
+
+    pub fn pathbuf(&self) -> Option<PathBuf> {
+        self.file_path
+            .as_ref()
+            .map(|arc| PathBuf::from(arc.as_str()))
+    }
+
+    pub fn set_path(&mut self, path: &Path) {
+        let path = absolute_path(path);
+        self.file_path = Some(Arc::new(path.to_string_lossy().to_string()));
+    }
+
+    /// Marks the current text as a separate undo step before a command edits it.
+    pub fn checkpoint(&mut self) {
+        self.history
+            .checkpoint(&self.text, self.selection.byte_range);
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `path` as tabs and the session store it: resolved against the working
+/// directory, and with symlinks and `..` followed when the file exists, so
+/// one file is never open under two names.
+pub fn absolute_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_owned())
+}
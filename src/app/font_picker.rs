@@ -0,0 +1,169 @@
+use druid::text::FontDescriptor;
+use druid::widget::{
+    Button, Checkbox, CrossAxisAlignment, Flex, Label, Painter, RadioGroup, Scroll, Stepper,
+    TextBox, ViewSwitcher,
+};
+use druid::{Key, LensExt, RenderContext, Target, Widget, WidgetExt, theme};
+
+use super::commands::CMD_SET_FONT;
+use super::state::{AppState, FontPickerState, FontSettings};
+use crate::fonts;
+use crate::theme::{MUTED_TEXT, PANEL_BACKGROUND, SELECTION};
+
+const PREVIEW_FONT: Key<FontDescriptor> = Key::new("rustpad.font-picker.preview");
+const PREVIEW_TEXT: &str =
+    "The quick brown fox jumps over the lazy dog.\n0123456789 {}[]()<>;:=+-*/ il1I O0";
+const LIST_SIZE: (f64, f64) = (260.0, 220.0);
+
+pub fn build_font_picker() -> impl Widget<AppState> {
+    ViewSwitcher::new(
+        |data: &AppState, _| data.font_picker.visible,
+        |visible, _data, _env| {
+            if *visible {
+                picker_view().boxed()
+            } else {
+                Label::new("").boxed()
+            }
+        },
+    )
+}
+
+fn picker_view() -> impl Widget<AppState> {
+    let draft = AppState::font_picker.then(FontPickerState::draft);
+
+    let families = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            TextBox::new()
+                .with_placeholder("Filter families")
+                .lens(AppState::font_picker.then(FontPickerState::filter))
+                .expand_width(),
+        )
+        .with_spacer(6.0)
+        .with_child(
+            ViewSwitcher::new(
+                // Keyed on the filter and the list being ready, not the selection,
+                // so picking a family keeps the scroll position.
+                |data: &AppState, _| (data.font_picker.filter.clone(), data.font_picker.loaded),
+                |(filter, _), _data, _env| Scroll::new(family_list(filter)).vertical().boxed(),
+            )
+            .fix_size(LIST_SIZE.0, LIST_SIZE.1)
+            .border(theme::BORDER_DARK, 1.0),
+        )
+        .fix_width(LIST_SIZE.0);
+
+    let style = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Weight"))
+        .with_child(
+            RadioGroup::column(FontSettings::WEIGHTS.to_vec())
+                .lens(draft.clone().then(FontSettings::weight)),
+        )
+        .with_spacer(6.0)
+        .with_child(Checkbox::new("Italic").lens(draft.clone().then(FontSettings::italic)))
+        .with_spacer(6.0)
+        .with_child(
+            Flex::row()
+                .with_child(Label::new("Size"))
+                .with_spacer(8.0)
+                .with_child(Label::dynamic(|data: &AppState, _| {
+                    format!("{}", data.font_picker.draft.size)
+                }))
+                .with_child(
+                    Stepper::new()
+                        .with_range(FontSettings::MIN_SIZE, FontSettings::MAX_SIZE)
+                        .with_step(1.0)
+                        .lens(draft.then(FontSettings::size)),
+                ),
+        );
+
+    let preview = Label::new(PREVIEW_TEXT)
+        .with_font(PREVIEW_FONT)
+        .padding(8.0)
+        .expand_width()
+        .background(theme::BACKGROUND_LIGHT)
+        .border(theme::BORDER_DARK, 1.0)
+        .env_scope(|env, data: &AppState| {
+            env.set(PREVIEW_FONT, data.font_picker.draft.descriptor());
+        });
+
+    let buttons =
+        Flex::row()
+            .with_child(Button::new("OK").on_click(|ctx, data: &mut AppState, _| {
+                ctx.submit_command(
+                    CMD_SET_FONT
+                        .with(data.font_picker.draft.clone())
+                        .to(Target::Global),
+                );
+                data.font_picker = FontPickerState::default();
+            }))
+            .with_spacer(8.0)
+            .with_child(Button::new("Cancel").on_click(|_, data: &mut AppState, _| {
+                data.font_picker = FontPickerState::default()
+            }));
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(families)
+                .with_spacer(12.0)
+                .with_flex_child(
+                    Flex::column()
+                        .cross_axis_alignment(CrossAxisAlignment::Start)
+                        .with_child(style)
+                        .with_spacer(8.0)
+                        .with_child(preview),
+                    1.0,
+                ),
+        )
+        .with_spacer(8.0)
+        .with_child(buttons)
+        .padding(8.0)
+        .border(theme::BORDER_DARK, 1.0)
+        .background(PANEL_BACKGROUND)
+}
+
+fn family_list(filter: &str) -> Flex<AppState> {
+    let filter = filter.to_lowercase();
+    let mut list = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    let Some(families) = fonts::installed_families() else {
+        list.add_child(
+            Label::new("Loading fonts…")
+                .with_text_color(MUTED_TEXT)
+                .padding((6.0, 2.0)),
+        );
+        return list;
+    };
+    for family in families
+        .iter()
+        .filter(|family| family.name.to_lowercase().contains(&filter))
+    {
+        let name = family.name.clone();
+        let highlighted = family.name.clone();
+        let mut row = Flex::row().with_child(Label::new(family.name.as_str()));
+        if family.monospace {
+            row.add_spacer(6.0);
+            row.add_child(
+                Label::new("monospace")
+                    .with_text_color(MUTED_TEXT)
+                    .with_text_size(11.0),
+            );
+        }
+        list.add_child(
+            row.padding((6.0, 2.0))
+                .expand_width()
+                .background(Painter::new(move |ctx, data: &AppState, env| {
+                    if data.font_picker.draft.family == highlighted {
+                        let rect = ctx.size().to_rect();
+                        ctx.fill(rect, &env.get(SELECTION));
+                    }
+                }))
+                .on_click(move |_, data: &mut AppState, _| {
+                    data.font_picker.draft.family = name.clone();
+                }),
+        );
+    }
+    list
+}
@@ -1,132 +1,315 @@
-use druid::commands::SHOW_OPEN_PANEL;
-use druid::keyboard_types::Key;
-use druid::menu::MenuEventCtx;
-use druid::{Env, FileDialogOptions, FileSpec, Menu, MenuItem, SysMods, WindowId, commands};
+use druid::{Command, Data, Env, HotKey, Menu, MenuItem, WindowId, commands};
 
+use super::actions;
 use super::commands::{
-    CMD_DECREASE_FONT, CMD_EXIT, CMD_FIND_NEXT, CMD_FIND_PREV, CMD_INCREASE_FONT, CMD_NEW_FILE,
-    CMD_SAVE_AS, CMD_SAVE_FILE, CMD_SET_FONT, CMD_SHOW_ABOUT, CMD_SHOW_SEARCH, CMD_TIME_DATE,
-    CMD_TOGGLE_STATUS, CMD_TOGGLE_WRAP,
+    CMD_ADD_NEXT_OCCURRENCE, CMD_CLEAR_RECENT, CMD_CLOSE_TAB, CMD_CONVERT_CASE,
+    CMD_CONVERT_INDENTATION, CMD_DECREASE_FONT, CMD_EXIT, CMD_FIND_NEXT, CMD_FIND_PREV,
+    CMD_HARD_WRAP, CMD_INCREASE_FONT, CMD_LINE_OPERATION, CMD_NEW_FILE, CMD_NEXT_TAB,
+    CMD_OPEN_RECENT, CMD_PREV_TAB, CMD_SAVE_AS, CMD_SAVE_FILE, CMD_SET_TAB_WIDTH, CMD_SET_THEME,
+    CMD_SHOW_ABOUT, CMD_SHOW_FONT_PICKER, CMD_SHOW_PALETTE, CMD_SHOW_SEARCH, CMD_TIME_DATE,
+    CMD_TOGGLE_COMMENT, CMD_TOGGLE_CURRENT_LINE, CMD_TOGGLE_INSERT_SPACES, CMD_TOGGLE_LINE_NUMBERS,
+    CMD_TOGGLE_STATUS, CMD_TOGGLE_WHITESPACE, CMD_TOGGLE_WRAP, CMD_TOGGLE_WRAP_COLUMN, CMD_ZOOM_IN,
+    CMD_ZOOM_OUT, CMD_ZOOM_RESET,
 };
-use super::state::{AppState, FontChoice, SearchMode};
+use super::state::{AppState, SearchMode};
+use crate::case::Case;
+use crate::editor::{
+    ADD_CARET_ABOVE, ADD_CARET_BELOW, FOLD_ALL, GO_TO_MATCHING_BRACKET, SELECT_TO_MATCHING_BRACKET,
+    UNFOLD_ALL,
+};
+use crate::lines::{LineOperation, SortKey};
+
+const TAB_WIDTHS: [u8; 4] = [2, 3, 4, 8];
 
-pub fn make_menu(_window: Option<WindowId>, _state: &AppState, _env: &Env) -> Menu<AppState> {
+pub fn make_menu(_window: Option<WindowId>, data: &AppState, _env: &Env) -> Menu<AppState> {
     let file = Menu::new("File")
         .entry(
             MenuItem::new("New")
                 .command(CMD_NEW_FILE)
-                .hotkey(SysMods::Cmd, "n"),
+                .dynamic_hotkey(bound("new-file")),
         )
         .entry(
             MenuItem::new("Open...")
-                .hotkey(SysMods::Cmd, "o")
-                .on_activate(|ctx, _, _| show_open_dialog(ctx)),
+                .on_activate(|ctx, _, _| ctx.submit_command(action_command("open-file")))
+                .dynamic_hotkey(bound("open-file")),
         )
+        .entry(recent_menu(data))
         .entry(
             MenuItem::new("Save")
                 .command(CMD_SAVE_FILE)
-                .hotkey(SysMods::Cmd, "s"),
+                .dynamic_hotkey(bound("save")),
         )
         .entry(
             MenuItem::new("Save As...")
                 .command(CMD_SAVE_AS)
-                .hotkey(SysMods::CmdShift, Key::Character("F".into())),
+                .dynamic_hotkey(bound("save-as")),
+        )
+        .entry(
+            MenuItem::new("Close Tab")
+                .command(CMD_CLOSE_TAB)
+                .dynamic_hotkey(bound("close-tab")),
         )
         .separator()
-        .entry(MenuItem::new("Exit").command(CMD_EXIT));
+        .entry(
+            MenuItem::new("Exit")
+                .command(CMD_EXIT)
+                .dynamic_hotkey(bound("exit")),
+        );
 
     let edit = Menu::new("Edit")
         .entry(
             MenuItem::new("Undo")
                 .command(commands::UNDO)
-                .hotkey(SysMods::Cmd, "z"),
+                .dynamic_hotkey(bound("undo")),
         )
         .entry(
             MenuItem::new("Redo")
                 .command(commands::REDO)
-                .hotkey(SysMods::CmdShift, "z"),
+                .dynamic_hotkey(bound("redo")),
         )
         .separator()
         .entry(
             MenuItem::new("Cut")
                 .command(commands::CUT)
-                .hotkey(SysMods::Cmd, "x"),
+                .dynamic_hotkey(bound("cut")),
         )
         .entry(
             MenuItem::new("Copy")
                 .command(commands::COPY)
-                .hotkey(SysMods::Cmd, "c"),
+                .dynamic_hotkey(bound("copy")),
         )
         .entry(
             MenuItem::new("Paste")
                 .command(commands::PASTE)
-                .hotkey(SysMods::Cmd, "v"),
+                .dynamic_hotkey(bound("paste")),
         )
         .separator()
         .entry(
             MenuItem::new("Find...")
                 .command(CMD_SHOW_SEARCH.with(SearchMode::Find))
-                .hotkey(SysMods::Cmd, "f"),
+                .dynamic_hotkey(bound("find")),
         )
         .entry(
             MenuItem::new("Find Next")
                 .command(CMD_FIND_NEXT)
-                .hotkey(SysMods::None, Key::F3),
+                .dynamic_hotkey(bound("find-next")),
         )
         .entry(
             MenuItem::new("Find Previous")
                 .command(CMD_FIND_PREV)
-                .hotkey(SysMods::Shift, Key::F3),
+                .dynamic_hotkey(bound("find-previous")),
         )
         .entry(
             MenuItem::new("Replace...")
                 .command(CMD_SHOW_SEARCH.with(SearchMode::Replace))
-                .hotkey(SysMods::Cmd, "h"),
+                .dynamic_hotkey(bound("replace")),
         )
         .entry(
             MenuItem::new("Go To...")
                 .command(CMD_SHOW_SEARCH.with(SearchMode::GoTo))
-                .hotkey(SysMods::Cmd, "g"),
+                .dynamic_hotkey(bound("go-to")),
+        )
+        .entry(
+            MenuItem::new("Go to Matching Bracket")
+                .command(GO_TO_MATCHING_BRACKET)
+                .dynamic_hotkey(bound("go-to-matching-bracket")),
+        )
+        .entry(
+            MenuItem::new("Select to Matching Bracket")
+                .command(SELECT_TO_MATCHING_BRACKET)
+                .dynamic_hotkey(bound("select-to-matching-bracket")),
         )
         .separator()
         .entry(
             MenuItem::new("Select All")
                 .command(commands::SELECT_ALL)
-                .hotkey(SysMods::Cmd, "a"),
+                .dynamic_hotkey(bound("select-all")),
+        )
+        .entry(
+            MenuItem::new("Add Next Occurrence")
+                .command(CMD_ADD_NEXT_OCCURRENCE)
+                .dynamic_hotkey(bound("add-next-occurrence")),
+        )
+        .entry(
+            MenuItem::new("Add Caret Above")
+                .command(ADD_CARET_ABOVE)
+                .dynamic_hotkey(bound("add-caret-above")),
+        )
+        .entry(
+            MenuItem::new("Add Caret Below")
+                .command(ADD_CARET_BELOW)
+                .dynamic_hotkey(bound("add-caret-below")),
+        )
+        .separator()
+        .entry(
+            MenuItem::new("Duplicate Line/Selection")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Duplicate))
+                .dynamic_hotkey(bound("duplicate-line")),
+        )
+        .entry(
+            MenuItem::new("Move Line Up")
+                .command(CMD_LINE_OPERATION.with(LineOperation::MoveUp))
+                .dynamic_hotkey(bound("move-line-up")),
+        )
+        .entry(
+            MenuItem::new("Move Line Down")
+                .command(CMD_LINE_OPERATION.with(LineOperation::MoveDown))
+                .dynamic_hotkey(bound("move-line-down")),
+        )
+        .entry(
+            MenuItem::new("Delete Line")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Delete))
+                .dynamic_hotkey(bound("delete-line")),
+        )
+        .entry(
+            MenuItem::new("Join Lines")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Join))
+                .dynamic_hotkey(bound("join-lines")),
+        )
+        .entry(
+            MenuItem::new("Insert Line Above")
+                .command(CMD_LINE_OPERATION.with(LineOperation::InsertAbove))
+                .dynamic_hotkey(bound("insert-line-above")),
+        )
+        .entry(
+            MenuItem::new("Insert Line Below")
+                .command(CMD_LINE_OPERATION.with(LineOperation::InsertBelow))
+                .dynamic_hotkey(bound("insert-line-below")),
+        )
+        .entry(lines_menu())
+        .entry(
+            MenuItem::new("Toggle Line Comment")
+                .command(CMD_TOGGLE_COMMENT.with(false))
+                .dynamic_hotkey(bound("toggle-line-comment")),
         )
+        .entry(
+            MenuItem::new("Toggle Block Comment")
+                .command(CMD_TOGGLE_COMMENT.with(true))
+                .dynamic_hotkey(bound("toggle-block-comment")),
+        )
+        .separator()
         .entry(
             MenuItem::new("Time/Date")
                 .command(CMD_TIME_DATE)
-                .hotkey(SysMods::CmdShift, "T"),
+                .dynamic_hotkey(bound("insert-time-date")),
         );
 
-    let font_menu = Menu::new("Font")
-        .entry(MenuItem::new("Consolas").command(CMD_SET_FONT.with(FontChoice::Consolas)))
-        .entry(MenuItem::new("Courier").command(CMD_SET_FONT.with(FontChoice::Courier)))
-        .entry(MenuItem::new("Arial").command(CMD_SET_FONT.with(FontChoice::Arial)))
-        .entry(MenuItem::new("Times").command(CMD_SET_FONT.with(FontChoice::Times)));
-
     let format = Menu::new("Format")
-        .entry(MenuItem::new("Word Wrap").command(CMD_TOGGLE_WRAP))
+        .entry(
+            MenuItem::new("Word Wrap")
+                .command(CMD_TOGGLE_WRAP)
+                .dynamic_hotkey(bound("toggle-word-wrap")),
+        )
+        .entry(
+            MenuItem::new("Wrap at Column")
+                .command(CMD_TOGGLE_WRAP_COLUMN)
+                .dynamic_hotkey(bound("toggle-wrap-at-column")),
+        )
+        .entry(
+            MenuItem::new("Hard Wrap Selection")
+                .command(CMD_HARD_WRAP)
+                .dynamic_hotkey(bound("hard-wrap-selection")),
+        )
+        .entry(case_menu())
+        .entry(indentation_menu())
         .separator()
         .entry(
             MenuItem::new("Increase Font Size")
                 .command(CMD_INCREASE_FONT)
-                .hotkey(SysMods::Cmd, "="),
+                .dynamic_hotkey(bound("increase-font-size")),
         )
         .entry(
             MenuItem::new("Decrease Font Size")
                 .command(CMD_DECREASE_FONT)
-                .hotkey(SysMods::Cmd, "-"),
+                .dynamic_hotkey(bound("decrease-font-size")),
         )
         .separator()
-        .entry(font_menu);
+        .entry(
+            MenuItem::new("Font...")
+                .command(CMD_SHOW_FONT_PICKER)
+                .dynamic_hotkey(bound("choose-font")),
+        );
 
-    let view = Menu::new("View").entry(MenuItem::new("Status Bar").command(CMD_TOGGLE_STATUS));
+    let zoom = Menu::new("Zoom")
+        .entry(
+            MenuItem::new("Zoom In")
+                .command(CMD_ZOOM_IN)
+                .dynamic_hotkey(bound("zoom-in")),
+        )
+        .entry(
+            MenuItem::new("Zoom Out")
+                .command(CMD_ZOOM_OUT)
+                .dynamic_hotkey(bound("zoom-out")),
+        )
+        .entry(
+            MenuItem::new("Reset Zoom")
+                .command(CMD_ZOOM_RESET)
+                .dynamic_hotkey(bound("zoom-reset")),
+        );
 
-    let help = Menu::new("Help").entry(MenuItem::new("About RustPad").command(CMD_SHOW_ABOUT));
+    let view = Menu::new("View")
+        .entry(
+            MenuItem::new("Command Palette...")
+                .command(CMD_SHOW_PALETTE)
+                .dynamic_hotkey(bound("command-palette")),
+        )
+        .separator()
+        .entry(
+            MenuItem::new("Status Bar")
+                .command(CMD_TOGGLE_STATUS)
+                .dynamic_hotkey(bound("toggle-status-bar")),
+        )
+        .entry(
+            MenuItem::new("Line Numbers")
+                .command(CMD_TOGGLE_LINE_NUMBERS)
+                .dynamic_hotkey(bound("toggle-line-numbers")),
+        )
+        .entry(
+            MenuItem::new("Highlight Current Line")
+                .command(CMD_TOGGLE_CURRENT_LINE)
+                .dynamic_hotkey(bound("toggle-current-line")),
+        )
+        .entry(
+            MenuItem::new("Show Whitespace")
+                .command(CMD_TOGGLE_WHITESPACE)
+                .dynamic_hotkey(bound("toggle-whitespace")),
+        )
+        .separator()
+        .entry(
+            MenuItem::new("Fold All")
+                .command(FOLD_ALL)
+                .dynamic_hotkey(bound("fold-all")),
+        )
+        .entry(
+            MenuItem::new("Unfold All")
+                .command(UNFOLD_ALL)
+                .dynamic_hotkey(bound("unfold-all")),
+        )
+        .separator()
+        .entry(zoom)
+        .entry(theme_menu(data))
+        .separator()
+        .entry(
+            MenuItem::new("Next Tab")
+                .command(CMD_NEXT_TAB)
+                .dynamic_hotkey(bound("next-tab")),
+        )
+        .entry(
+            MenuItem::new("Previous Tab")
+                .command(CMD_PREV_TAB)
+                .dynamic_hotkey(bound("previous-tab")),
+        );
+
+    let help = Menu::new("Help").entry(
+        MenuItem::new("About RustPad")
+            .command(CMD_SHOW_ABOUT)
+            .dynamic_hotkey(bound("about")),
+    );
 
     Menu::empty()
+        .rebuild_on(|old: &AppState, data: &AppState, _| {
+            !old.recent_files.same(&data.recent_files) || !old.themes.same(&data.themes)
+        })
         .entry(file)
         .entry(edit)
         .entry(format)
@@ -134,10 +317,183 @@ pub fn make_menu(_window: Option<WindowId>, _state: &AppState, _env: &Env) -> Me
         .entry(help)
 }
 
-fn show_open_dialog(ctx: &mut MenuEventCtx) {
-    let options = FileDialogOptions::new()
-        .allowed_types(vec![FileSpec::new("Text", &["txt", "md", "rs", ""])])
-        .name_label("Document")
-        .title("Open");
-    ctx.submit_command(SHOW_OPEN_PANEL.with(options));
+fn lines_menu() -> Menu<AppState> {
+    Menu::new("Lines")
+        .entry(
+            MenuItem::new("Sort Ascending")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Sort {
+                    key: SortKey::Text,
+                    descending: false,
+                }))
+                .dynamic_hotkey(bound("sort-lines-ascending")),
+        )
+        .entry(
+            MenuItem::new("Sort Descending")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Sort {
+                    key: SortKey::Text,
+                    descending: true,
+                }))
+                .dynamic_hotkey(bound("sort-lines-descending")),
+        )
+        .entry(
+            MenuItem::new("Sort Ignoring Case")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Sort {
+                    key: SortKey::CaseInsensitive,
+                    descending: false,
+                }))
+                .dynamic_hotkey(bound("sort-lines-case-insensitive")),
+        )
+        .entry(
+            MenuItem::new("Sort Naturally")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Sort {
+                    key: SortKey::Natural,
+                    descending: false,
+                }))
+                .dynamic_hotkey(bound("sort-lines-natural")),
+        )
+        .entry(
+            MenuItem::new("Sort by Column...")
+                .command(CMD_SHOW_SEARCH.with(SearchMode::SortLines))
+                .dynamic_hotkey(bound("sort-lines-by-column")),
+        )
+        .separator()
+        .entry(
+            MenuItem::new("Remove Duplicate Lines")
+                .command(CMD_LINE_OPERATION.with(LineOperation::RemoveDuplicates))
+                .dynamic_hotkey(bound("remove-duplicate-lines")),
+        )
+        .entry(
+            MenuItem::new("Remove Blank Lines")
+                .command(CMD_LINE_OPERATION.with(LineOperation::RemoveBlank))
+                .dynamic_hotkey(bound("remove-blank-lines")),
+        )
+        .entry(
+            MenuItem::new("Shuffle Lines")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Shuffle))
+                .dynamic_hotkey(bound("shuffle-lines")),
+        )
+        .entry(
+            MenuItem::new("Reverse Lines")
+                .command(CMD_LINE_OPERATION.with(LineOperation::Reverse))
+                .dynamic_hotkey(bound("reverse-lines")),
+        )
+}
+
+fn case_menu() -> Menu<AppState> {
+    Menu::new("Convert Case")
+        .entry(
+            MenuItem::new("UPPERCASE")
+                .command(CMD_CONVERT_CASE.with(Case::Upper))
+                .dynamic_hotkey(bound("convert-to-uppercase")),
+        )
+        .entry(
+            MenuItem::new("lowercase")
+                .command(CMD_CONVERT_CASE.with(Case::Lower))
+                .dynamic_hotkey(bound("convert-to-lowercase")),
+        )
+        .entry(
+            MenuItem::new("Title Case")
+                .command(CMD_CONVERT_CASE.with(Case::Title))
+                .dynamic_hotkey(bound("convert-to-title-case")),
+        )
+        .entry(
+            MenuItem::new("Sentence case")
+                .command(CMD_CONVERT_CASE.with(Case::Sentence))
+                .dynamic_hotkey(bound("convert-to-sentence-case")),
+        )
+        .entry(
+            MenuItem::new("snake_case")
+                .command(CMD_CONVERT_CASE.with(Case::Snake))
+                .dynamic_hotkey(bound("convert-to-snake-case")),
+        )
+        .entry(
+            MenuItem::new("camelCase")
+                .command(CMD_CONVERT_CASE.with(Case::Camel))
+                .dynamic_hotkey(bound("convert-to-camel-case")),
+        )
+        .entry(
+            MenuItem::new("kebab-case")
+                .command(CMD_CONVERT_CASE.with(Case::Kebab))
+                .dynamic_hotkey(bound("convert-to-kebab-case")),
+        )
+        .entry(
+            MenuItem::new("iNVERT cASE")
+                .command(CMD_CONVERT_CASE.with(Case::Invert))
+                .dynamic_hotkey(bound("invert-case")),
+        )
+}
+
+fn indentation_menu() -> Menu<AppState> {
+    let mut tab_width = Menu::new("Tab Width");
+    for width in TAB_WIDTHS {
+        tab_width = tab_width.entry(
+            MenuItem::new(width.to_string())
+                .command(CMD_SET_TAB_WIDTH.with(width))
+                .selected_if(move |data: &AppState, _| {
+                    data.indentation().width == usize::from(width)
+                }),
+        );
+    }
+    Menu::new("Indentation")
+        .entry(
+            MenuItem::new("Insert Spaces")
+                .command(CMD_TOGGLE_INSERT_SPACES)
+                .selected_if(|data: &AppState, _| !data.indentation().tabs)
+                .dynamic_hotkey(bound("toggle-insert-spaces")),
+        )
+        .entry(tab_width)
+        .separator()
+        .entry(
+            MenuItem::new("Convert Indentation to Spaces")
+                .command(CMD_CONVERT_INDENTATION.with(false))
+                .dynamic_hotkey(bound("convert-indentation-to-spaces")),
+        )
+        .entry(
+            MenuItem::new("Convert Indentation to Tabs")
+                .command(CMD_CONVERT_INDENTATION.with(true))
+                .dynamic_hotkey(bound("convert-indentation-to-tabs")),
+        )
+}
+
+fn theme_menu(data: &AppState) -> Menu<AppState> {
+    let mut menu = Menu::new("Theme");
+    for theme in data.themes.iter() {
+        let name = theme.name.clone();
+        menu = menu.entry(
+            MenuItem::new(theme.name.as_str())
+                .command(CMD_SET_THEME.with(name.clone()))
+                .selected_if(move |data: &AppState, _| data.theme.name == name),
+        );
+    }
+    menu
+}
+
+fn recent_menu(data: &AppState) -> Menu<AppState> {
+    let mut menu = Menu::new("Open Recent");
+    if data.recent_files.is_empty() {
+        menu = menu.entry(MenuItem::new("No Recent Files").enabled(false));
+    }
+    for (index, path) in data.recent_files.iter().enumerate() {
+        menu = menu.entry(
+            MenuItem::new(format!("{} {}", index + 1, path))
+                .command(CMD_OPEN_RECENT.with(path.clone())),
+        );
+    }
+    menu.separator().entry(
+        MenuItem::new("Clear Recent")
+            .command(CMD_CLEAR_RECENT)
+            .dynamic_hotkey(bound("clear-recent"))
+            .enabled_if(|data: &AppState, _| !data.recent_files.is_empty()),
+    )
+}
+
+/// Looks up the current key binding for a menu item's action.
+fn bound(id: &'static str) -> impl FnMut(&AppState, &Env) -> Option<HotKey> {
+    move |data, _| data.keymap.hotkey(id)
+}
+
+fn action_command(id: &str) -> Command {
+    actions::find(id)
+        .map(|action| action.command())
+        .expect("menu entries refer to registered actions")
 }
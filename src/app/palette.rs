@@ -0,0 +1,195 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+use druid::widget::{BackgroundBrush, Controller, Flex, Label, Scroll, TextBox, ViewSwitcher};
+use druid::{
+    Color, Env, Event, EventCtx, KbKey, LensExt, LifeCycle, LifeCycleCtx, Selector, Target, Widget,
+    WidgetExt, theme,
+};
+
+use super::actions::{ACTIONS, Action};
+use super::commands::EDITOR_ID;
+use super::state::{AppState, PaletteState};
+use crate::editor::REQUEST_EDITOR_FOCUS;
+use crate::fuzzy;
+use crate::keymap::Keymap;
+use crate::theme::{MUTED_TEXT, PANEL_BACKGROUND, SELECTION};
+
+const RESULTS_HEIGHT: f64 = 280.0;
+/// Description matches rank below any match on the command name.
+const DESCRIPTION_PENALTY: i64 = 50;
+
+const FOCUS_QUERY: Selector<()> = Selector::new("rustpad.palette.focus-query");
+
+pub fn build_palette() -> impl Widget<AppState> {
+    // Rebuilt on every open so the query box grabs focus again.
+    ViewSwitcher::new(
+        |data: &AppState, _| data.palette.visible,
+        |visible, _data, _env| {
+            if *visible {
+                palette_view().boxed()
+            } else {
+                Label::new("").boxed()
+            }
+        },
+    )
+}
+
+/// Actions matching `query`, best first.
+pub fn matches(query: &str) -> Vec<&'static Action> {
+    let mut scored: Vec<(i64, &'static Action)> = ACTIONS
+        .iter()
+        .filter_map(|action| {
+            let by_name = fuzzy::score(query, action.name);
+            let by_description =
+                fuzzy::score(query, action.description).map(|score| score - DESCRIPTION_PENALTY);
+            by_name.or(by_description).map(|score| (score, action))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    scored.into_iter().map(|(_, action)| action).collect()
+}
+
+fn palette_view() -> impl Widget<AppState> {
+    let query = TextBox::new()
+        .with_placeholder("Type a command name")
+        .lens(AppState::palette.then(PaletteState::query))
+        .expand_width()
+        .controller(PaletteKeys);
+
+    let results = ViewSwitcher::new(
+        |data: &AppState, _| {
+            (
+                data.palette.query.clone(),
+                data.palette.selected,
+                data.keymap.clone(),
+            )
+        },
+        |(query, selected, keymap), _data, _env| {
+            Scroll::new(results_view(query, *selected, keymap))
+                .vertical()
+                .boxed()
+        },
+    )
+    .fix_height(RESULTS_HEIGHT);
+
+    Flex::column()
+        .with_child(query)
+        .with_spacer(6.0)
+        .with_child(results)
+        .padding(8.0)
+        .border(theme::BORDER_DARK, 1.0)
+        .background(PANEL_BACKGROUND)
+}
+
+fn results_view(query: &str, selected: usize, keymap: &Arc<Keymap>) -> Flex<AppState> {
+    let found = matches(query);
+    let mut list = Flex::column();
+    if found.is_empty() {
+        list.add_child(Label::new("No matching commands").with_text_color(MUTED_TEXT));
+    }
+    for (index, action) in found.into_iter().enumerate() {
+        let keys = keymap
+            .binding(action.id)
+            .map(|binding| binding.to_string())
+            .unwrap_or_default();
+        let row = Flex::row()
+            .with_child(Label::new(action.name))
+            .with_spacer(12.0)
+            .with_child(
+                Label::new(action.description)
+                    .with_text_color(MUTED_TEXT)
+                    .with_text_size(12.0),
+            )
+            .with_flex_spacer(1.0)
+            .with_child(Label::new(keys).with_text_color(MUTED_TEXT))
+            .padding((6.0, 3.0))
+            .expand_width()
+            .background(if index == selected {
+                BackgroundBrush::from(SELECTION)
+            } else {
+                BackgroundBrush::from(Color::TRANSPARENT)
+            })
+            .on_click(move |ctx, data: &mut AppState, _| run(ctx, data, action));
+        list.add_child(row);
+    }
+    list
+}
+
+fn run(ctx: &mut EventCtx, data: &mut AppState, action: &Action) {
+    close(ctx, data);
+    ctx.submit_command(action.command());
+}
+
+fn close(ctx: &mut EventCtx, data: &mut AppState) {
+    data.palette = PaletteState::default();
+    ctx.submit_command(REQUEST_EDITOR_FOCUS.to(EDITOR_ID));
+}
+
+/// Keyboard navigation for the query box: arrows move the highlight, Enter
+/// runs it and Escape dismisses the palette.
+struct PaletteKeys;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for PaletteKeys {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(FOCUS_QUERY) => {
+                ctx.request_focus();
+                ctx.set_handled();
+                return;
+            }
+            Event::KeyDown(key) => {
+                let count = matches(&data.palette.query).len();
+                match key.key {
+                    KbKey::ArrowDown => {
+                        data.palette.selected = (data.palette.selected + 1).min(count.max(1) - 1);
+                    }
+                    KbKey::ArrowUp => {
+                        data.palette.selected = data.palette.selected.saturating_sub(1);
+                    }
+                    KbKey::Enter => {
+                        if let Some(action) =
+                            matches(&data.palette.query).get(data.palette.selected)
+                        {
+                            run(ctx, data, action);
+                        }
+                    }
+                    KbKey::Escape => close(ctx, data),
+                    _ => {
+                        let before = data.palette.query.clone();
+                        child.event(ctx, event, data, env);
+                        if data.palette.query != before {
+                            data.palette.selected = 0;
+                        }
+                        return;
+                    }
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.submit_command(FOCUS_QUERY.to(Target::Widget(ctx.widget_id())));
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
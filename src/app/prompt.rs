@@ -0,0 +1,75 @@
+use druid::text::{FontDescriptor, FontFamily};
+use druid::widget::{
+    Button, CrossAxisAlignment, Either, Flex, Label, LineBreaking, Scroll, ViewSwitcher,
+};
+use druid::{Target, Widget, WidgetExt, theme};
+
+use crate::theme::{BANNER_BACKGROUND, BANNER_TEXT};
+
+use super::commands::CMD_PROMPT_RESPONSE;
+use super::state::{AppState, PromptKind};
+
+pub fn build_prompt_panel() -> impl Widget<AppState> {
+    Either::new(
+        |data: &AppState, _| data.prompt.is_some(),
+        ViewSwitcher::new(
+            |data: &AppState, _| data.prompt.as_ref().map(|prompt| prompt.kind),
+            |kind, _data, _env| match kind {
+                Some(kind) => prompt_view(*kind).boxed(),
+                None => Label::new("").boxed(),
+            },
+        )
+        .padding(8.0)
+        .border(theme::BORDER_DARK, 1.0)
+        .background(BANNER_BACKGROUND)
+        .env_scope(|env, _| env.set(theme::TEXT_COLOR, env.get(BANNER_TEXT))),
+        Label::new(""),
+    )
+}
+
+fn prompt_view(kind: PromptKind) -> impl Widget<AppState> {
+    let mut buttons = Flex::row();
+    for (index, (label, choice)) in kind.choices().iter().copied().enumerate() {
+        if index > 0 {
+            buttons.add_spacer(8.0);
+        }
+        buttons.add_child(Button::new(label).on_click(move |ctx, _, _| {
+            ctx.submit_command(CMD_PROMPT_RESPONSE.with(choice).to(Target::Global))
+        }));
+    }
+
+    let detail = Scroll::new(
+        Label::dynamic(|data: &AppState, _| {
+            data.prompt
+                .as_ref()
+                .and_then(|prompt| prompt.detail.as_deref().cloned())
+                .unwrap_or_default()
+        })
+        .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+        .with_line_break_mode(LineBreaking::Overflow),
+    )
+    .fix_height(180.0)
+    .expand_width()
+    .border(theme::BORDER_DARK, 1.0);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            data.prompt
+                .as_ref()
+                .map(|prompt| prompt.message.clone())
+                .unwrap_or_default()
+        }))
+        .with_spacer(8.0)
+        .with_child(Either::new(
+            |data: &AppState, _| {
+                data.prompt
+                    .as_ref()
+                    .is_some_and(|prompt| prompt.detail.is_some())
+            },
+            detail,
+            Label::new(""),
+        ))
+        .with_spacer(8.0)
+        .with_child(buttons)
+}
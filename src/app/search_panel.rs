@@ -1,8 +1,13 @@
 use druid::widget::{Button, Checkbox, Either, Flex, Label, TextBox, ViewSwitcher};
-use druid::{Color, LensExt, Target, Widget, WidgetExt};
+use druid::{LensExt, Target, Widget, WidgetExt, theme};
 
-use super::commands::{CMD_FIND_NEXT, CMD_FIND_PREV, CMD_GO_TO, CMD_REPLACE_ALL, CMD_REPLACE_ONE};
-use super::state::{AppState, SearchMode, SearchPanelState};
+use super::commands::{
+    CMD_FIND_NEXT, CMD_FIND_PREV, CMD_GO_TO, CMD_REPLACE_ALL, CMD_REPLACE_ONE, CMD_SORT_BY_COLUMN,
+};
+use super::document::Document;
+use crate::theme::PANEL_BACKGROUND;
+
+use super::state::{ActiveDocument, AppState, SearchMode, SearchOptions, SearchPanelState};
 
 pub fn build_search_panel() -> impl Widget<AppState> {
     Either::new(
@@ -13,11 +18,12 @@ pub fn build_search_panel() -> impl Widget<AppState> {
                 SearchMode::Find => find_view().boxed(),
                 SearchMode::Replace => replace_view().boxed(),
                 SearchMode::GoTo => goto_view().boxed(),
+                SearchMode::SortLines => sort_view().boxed(),
             },
         )
         .padding(8.0)
-        .border(Color::grey(0.6), 1.0)
-        .background(Color::grey8(0xF5)),
+        .border(theme::BORDER_DARK, 1.0)
+        .background(PANEL_BACKGROUND),
         Label::new(""),
     )
 }
@@ -29,7 +35,11 @@ fn find_view() -> impl Widget<AppState> {
                 .with_child(Label::new("Find what:"))
                 .with_spacer(8.0)
                 .with_flex_child(
-                    TextBox::new().lens(AppState::search.then(SearchPanelState::query)),
+                    TextBox::new().lens(
+                        ActiveDocument
+                            .then(Document::search)
+                            .then(SearchPanelState::query),
+                    ),
                     1.0,
                 ),
         )
@@ -38,17 +48,17 @@ fn find_view() -> impl Widget<AppState> {
             Flex::row()
                 .with_child(
                     Checkbox::new("Match case")
-                        .lens(AppState::search.then(SearchPanelState::match_case)),
+                        .lens(AppState::search_options.then(SearchOptions::match_case)),
                 )
                 .with_spacer(12.0)
                 .with_child(
                     Checkbox::new("Wrap around")
-                        .lens(AppState::search.then(SearchPanelState::wrap)),
+                        .lens(AppState::search_options.then(SearchOptions::wrap)),
                 )
                 .with_spacer(12.0)
                 .with_child(
                     Checkbox::new("Search down")
-                        .lens(AppState::search.then(SearchPanelState::search_down)),
+                        .lens(AppState::search_options.then(SearchOptions::search_down)),
                 ),
         )
         .with_spacer(8.0)
@@ -78,7 +88,11 @@ fn replace_view() -> impl Widget<AppState> {
                 .with_child(Label::new("Find what:"))
                 .with_spacer(8.0)
                 .with_flex_child(
-                    TextBox::new().lens(AppState::search.then(SearchPanelState::query)),
+                    TextBox::new().lens(
+                        ActiveDocument
+                            .then(Document::search)
+                            .then(SearchPanelState::query),
+                    ),
                     1.0,
                 ),
         )
@@ -88,7 +102,11 @@ fn replace_view() -> impl Widget<AppState> {
                 .with_child(Label::new("Replace with:"))
                 .with_spacer(8.0)
                 .with_flex_child(
-                    TextBox::new().lens(AppState::search.then(SearchPanelState::replacement)),
+                    TextBox::new().lens(
+                        ActiveDocument
+                            .then(Document::search)
+                            .then(SearchPanelState::replacement),
+                    ),
                     1.0,
                 ),
         )
@@ -97,12 +115,12 @@ fn replace_view() -> impl Widget<AppState> {
             Flex::row()
                 .with_child(
                     Checkbox::new("Match case")
-                        .lens(AppState::search.then(SearchPanelState::match_case)),
+                        .lens(AppState::search_options.then(SearchOptions::match_case)),
                 )
                 .with_spacer(12.0)
                 .with_child(
                     Checkbox::new("Wrap around")
-                        .lens(AppState::search.then(SearchPanelState::wrap)),
+                        .lens(AppState::search_options.then(SearchOptions::wrap)),
                 ),
         )
         .with_spacer(8.0)
@@ -139,7 +157,11 @@ fn goto_view() -> impl Widget<AppState> {
                 .with_child(Label::new("Line number:"))
                 .with_spacer(8.0)
                 .with_flex_child(
-                    TextBox::new().lens(AppState::search.then(SearchPanelState::goto_line)),
+                    TextBox::new().lens(
+                        ActiveDocument
+                            .then(Document::search)
+                            .then(SearchPanelState::goto_line),
+                    ),
                     1.0,
                 ),
         )
@@ -157,3 +179,47 @@ fn goto_view() -> impl Widget<AppState> {
                 ),
         )
 }
+
+fn sort_view() -> impl Widget<AppState> {
+    Flex::column()
+        .with_child(
+            Flex::row()
+                .with_child(Label::new("Delimiter:"))
+                .with_spacer(8.0)
+                .with_flex_child(
+                    TextBox::new().with_placeholder("Whitespace").lens(
+                        ActiveDocument
+                            .then(Document::search)
+                            .then(SearchPanelState::sort_delimiter),
+                    ),
+                    1.0,
+                )
+                .with_spacer(12.0)
+                .with_child(Label::new("Column:"))
+                .with_spacer(8.0)
+                .with_flex_child(
+                    TextBox::new().lens(
+                        ActiveDocument
+                            .then(Document::search)
+                            .then(SearchPanelState::sort_column),
+                    ),
+                    1.0,
+                ),
+        )
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_child(Button::new("Sort Ascending").on_click(|ctx, _, _| {
+                    ctx.submit_command(CMD_SORT_BY_COLUMN.with(false).to(Target::Global))
+                }))
+                .with_spacer(8.0)
+                .with_child(Button::new("Sort Descending").on_click(|ctx, _, _| {
+                    ctx.submit_command(CMD_SORT_BY_COLUMN.with(true).to(Target::Global))
+                }))
+                .with_spacer(8.0)
+                .with_child(
+                    Button::new("Close")
+                        .on_click(|_, data: &mut AppState, _| data.search_visible = false),
+                ),
+        )
+}
@@ -1,129 +1,251 @@
-use std::path::PathBuf;
 use std::sync::Arc;
 
-use druid::text::{FontDescriptor, FontFamily};
+use druid::im::Vector;
+use druid::text::{FontDescriptor, FontStyle, FontWeight};
 use druid::{Data, Lens};
 
-use crate::editor::{EditorMetrics, SelectionState};
-use crate::search::SearchRequest;
+use crate::fonts;
+use crate::indent::{IndentStyle, Indentation};
+use crate::keymap::Keymap;
+use crate::recovery::RecoveredDocument;
+use crate::theme::Theme;
+
+use super::actions;
+use super::document::Document;
 
 #[derive(Clone, Data, Lens)]
 pub struct AppState {
-    pub text: String,
-    pub file_path: Option<Arc<String>>,
-    pub saved_snapshot: String,
+    pub documents: Vector<Document>,
+    pub active: usize,
     pub word_wrap: bool,
     pub show_status_bar: bool,
-    pub metrics: EditorMetrics,
-    pub selection: SelectionState,
+    pub show_line_numbers: bool,
+    pub highlight_current_line: bool,
+    pub show_whitespace: bool,
+    pub rulers: Arc<Vec<u16>>,
+    /// Wraps lines at `wrap_column` instead of the window edge.
+    pub wrap_at_column: bool,
+    pub wrap_column: u16,
+    pub tab_width: u8,
+    /// Indent with spaces rather than tabs, unless a document is detected to
+    /// use the other.
+    pub insert_spaces: bool,
     pub font: FontSettings,
+    /// Magnification applied on top of the font size, in percent.
+    pub zoom_percent: u16,
+    pub font_picker: FontPickerState,
+    pub theme: Theme,
+    /// Built-in themes followed by any found in the user's themes directory.
+    pub themes: Arc<Vec<Theme>>,
     pub info_message: Option<String>,
-    pub search: SearchPanelState,
     pub search_visible: bool,
     pub search_mode: SearchMode,
-    pub last_search: Option<SearchRequest>,
+    pub search_options: SearchOptions,
+    pub palette: PaletteState,
+    pub pending_recovery: Vector<RecoveredDocument>,
+    pub recent_files: Vector<Arc<String>>,
+    pub keymap: Arc<Keymap>,
+    pub prompt: Option<Prompt>,
+    /// Document id to close once a Save As started from the close prompt completes.
+    pub pending_close: Option<usize>,
 }
 
 impl AppState {
+    pub const MIN_ZOOM: u16 = 50;
+    pub const MAX_ZOOM: u16 = 400;
+    pub const ZOOM_STEP: u16 = 10;
+
     pub fn new() -> Self {
         Self {
-            text: String::new(),
-            file_path: None,
-            saved_snapshot: String::new(),
+            documents: Vector::unit(Document::new()),
+            active: 0,
             word_wrap: false,
             show_status_bar: true,
-            metrics: EditorMetrics::default(),
-            selection: SelectionState::default(),
+            show_line_numbers: true,
+            highlight_current_line: true,
+            show_whitespace: false,
+            rulers: Arc::new(vec![80, 100]),
+            wrap_at_column: false,
+            wrap_column: 80,
+            tab_width: 4,
+            insert_spaces: true,
             font: FontSettings::default(),
+            zoom_percent: 100,
+            font_picker: FontPickerState::default(),
+            theme: Theme::light(),
+            themes: Arc::new(Theme::built_in()),
             info_message: None,
-            search: SearchPanelState::default(),
             search_visible: false,
             search_mode: SearchMode::Find,
-            last_search: None,
+            search_options: SearchOptions::default(),
+            palette: PaletteState::default(),
+            pending_recovery: Vector::new(),
+            recent_files: Vector::new(),
+            keymap: Arc::new(actions::default_keymap()),
+            prompt: None,
+            pending_close: None,
         }
     }
 
-    pub fn display_name(&self) -> String {
-        self.file_path
-            .as_ref()
-            .and_then(|arc| {
-                PathBuf::from(arc.as_str())
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-            })
-            .unwrap_or_else(|| "Untitled".to_string())
+    /// Word wrap hides the status bar whatever the setting says.
+    pub fn status_bar_visible(&self) -> bool {
+        self.show_status_bar && !self.word_wrap
     }
 
-    pub fn is_dirty(&self) -> bool {
-        self.text != self.saved_snapshot
+    /// How the active document is indented: as detected when it was opened,
+    /// or as the settings say.
+    pub fn indentation(&self) -> Indentation {
+        let tab_width = usize::from(self.tab_width);
+        match self.doc().indent_style {
+            Some(IndentStyle::Tabs) => Indentation {
+                tabs: true,
+                width: tab_width,
+            },
+            Some(IndentStyle::Spaces(width)) => Indentation { tabs: false, width },
+            None => Indentation {
+                tabs: !self.insert_spaces,
+                width: tab_width,
+            },
+        }
+    }
+
+    pub fn doc(&self) -> &Document {
+        &self.documents[self.active]
     }
 
-    pub fn mark_saved(&mut self) {
-        self.saved_snapshot = self.text.clone();
+    pub fn doc_mut(&mut self) -> &mut Document {
+        &mut self.documents[self.active]
+    }
+
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        self.documents.iter().position(|doc| doc.id == id)
+    }
+
+    pub fn find_path(&self, path: &str) -> Option<usize> {
+        self.documents
+            .iter()
+            .position(|doc| doc.file_path.as_deref().is_some_and(|p| p.as_str() == path))
+    }
+
+    /// Adds a document and makes it active, taking over the active tab when
+    /// that tab is an untouched untitled buffer.
+    pub fn open_document(&mut self, document: Document) {
+        if self.doc().is_pristine() {
+            self.documents[self.active] = document;
+        } else {
+            self.active += 1;
+            self.documents.insert(self.active, document);
+        }
+    }
+
+    /// Removes a tab, keeping at least one (empty) document open.
+    pub fn close_document(&mut self, index: usize) -> Document {
+        let closed = self.documents.remove(index);
+        if self.documents.is_empty() {
+            self.documents.push_back(Document::new());
+        }
+        if self.active > index || self.active >= self.documents.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        closed
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.documents.iter().any(Document::is_dirty)
     }
 
     pub fn font_descriptor(&self) -> FontDescriptor {
-        self.font.descriptor()
+        let size = self.font.size * f64::from(self.zoom_percent) / 100.0;
+        self.font.descriptor().with_size(size)
     }
+}
 
-    pub fn pathbuf(&self) -> Option<PathBuf> {
-        self.file_path
-            .as_ref()
-            .map(|arc| PathBuf::from(arc.as_str()))
+/// Lens from the app state to whichever document is in the active tab.
+pub struct ActiveDocument;
+
+impl Lens<AppState, Document> for ActiveDocument {
+    fn with<V, F: FnOnce(&Document) -> V>(&self, data: &AppState, f: F) -> V {
+        f(data.doc())
+    }
+
+    fn with_mut<V, F: FnOnce(&mut Document) -> V>(&self, data: &mut AppState, f: F) -> V {
+        f(data.doc_mut())
     }
 }
 
-#[derive(Clone, Data, Lens)]
+#[derive(Clone, Data, Debug, Lens)]
 pub struct FontSettings {
-    pub choice: FontChoice,
+    pub family: String,
     pub size: f64,
+    /// CSS-style weight, 100 (thin) to 900 (black).
+    pub weight: u16,
+    pub italic: bool,
 }
 
 impl Default for FontSettings {
     fn default() -> Self {
         Self {
-            choice: FontChoice::Consolas,
+            family: fonts::FALLBACK_FAMILY.to_string(),
             size: 15.0,
+            weight: FontWeight::REGULAR.to_raw(),
+            italic: false,
         }
     }
 }
 
 impl FontSettings {
+    pub const MIN_SIZE: f64 = 8.0;
+    pub const MAX_SIZE: f64 = 48.0;
+    pub const WEIGHTS: [(&'static str, u16); 4] = [
+        ("Light", 300),
+        ("Regular", 400),
+        ("Medium", 500),
+        ("Bold", 700),
+    ];
+
     pub fn descriptor(&self) -> FontDescriptor {
-        let family = match self.choice {
-            FontChoice::Consolas => FontFamily::new_unchecked("Consolas"),
-            FontChoice::Courier => FontFamily::new_unchecked("Courier New"),
-            FontChoice::Arial => FontFamily::new_unchecked("Arial"),
-            FontChoice::Times => FontFamily::new_unchecked("Times New Roman"),
+        let style = if self.italic {
+            FontStyle::Italic
+        } else {
+            FontStyle::Regular
         };
-        FontDescriptor::new(family).with_size(self.size)
+        FontDescriptor::new(fonts::family(&self.family))
+            .with_size(self.size)
+            .with_weight(FontWeight::new(self.weight))
+            .with_style(style)
     }
 }
 
-#[derive(Clone, Copy, Data, PartialEq, Eq)]
-pub enum FontChoice {
-    Consolas,
-    Courier,
-    Arial,
-    Times,
+#[derive(Clone, Data, Default, Lens)]
+pub struct FontPickerState {
+    pub visible: bool,
+    pub filter: String,
+    /// Whether the installed families have been read.
+    pub loaded: bool,
+    /// The font being previewed; only applied when the user confirms.
+    pub draft: FontSettings,
 }
 
-#[derive(Clone, Data, Lens)]
+#[derive(Clone, Data, Default, Lens)]
 pub struct SearchPanelState {
     pub query: String,
     pub replacement: String,
     pub goto_line: String,
+    pub sort_delimiter: String,
+    pub sort_column: String,
+}
+
+/// Search flags shared by every tab.
+#[derive(Clone, Data, Lens)]
+pub struct SearchOptions {
     pub match_case: bool,
     pub search_down: bool,
     pub wrap: bool,
 }
 
-impl Default for SearchPanelState {
+impl Default for SearchOptions {
     fn default() -> Self {
         Self {
-            query: String::new(),
-            replacement: String::new(),
-            goto_line: String::new(),
             match_case: false,
             search_down: true,
             wrap: true,
@@ -131,9 +253,67 @@ impl Default for SearchPanelState {
     }
 }
 
+#[derive(Clone, Data, Default, Lens)]
+pub struct PaletteState {
+    pub visible: bool,
+    pub query: String,
+    /// Index into the filtered results.
+    pub selected: usize,
+}
+
 #[derive(Clone, Copy, Data, PartialEq, Eq)]
 pub enum SearchMode {
     Find,
     Replace,
     GoTo,
+    SortLines,
+}
+
+#[derive(Clone, Data)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    /// The document the question is about, by id, if any.
+    pub document: Option<usize>,
+    pub message: String,
+    pub detail: Option<Arc<String>>,
+}
+
+#[derive(Clone, Copy, Data, PartialEq, Eq)]
+pub enum PromptKind {
+    Recovery,
+    ExternalChange,
+    CloseDirty,
+}
+
+impl PromptKind {
+    pub fn choices(self) -> &'static [(&'static str, PromptChoice)] {
+        match self {
+            PromptKind::Recovery => &[
+                ("Restore", PromptChoice::Restore),
+                ("Discard", PromptChoice::Discard),
+            ],
+            PromptKind::ExternalChange => &[
+                ("Reload", PromptChoice::Reload),
+                ("Keep Mine", PromptChoice::KeepMine),
+                ("Show Diff", PromptChoice::ShowDiff),
+            ],
+            PromptKind::CloseDirty => &[
+                ("Save", PromptChoice::Save),
+                ("Don't Save", PromptChoice::DontSave),
+                ("Cancel", PromptChoice::Cancel),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Data, Debug, PartialEq, Eq)]
+pub enum PromptChoice {
+    Restore,
+    Discard,
+    Reload,
+    KeepMine,
+    ShowDiff,
+    Save,
+    DontSave,
+    Cancel,
 }
@@ -0,0 +1,174 @@
+use druid::kurbo::Line;
+use druid::widget::prelude::*;
+use druid::{MouseButton, Point, Rect, TextLayout, theme};
+
+use super::commands::CMD_CLOSE_TAB;
+use super::document::Document;
+use super::state::AppState;
+
+const TAB_HEIGHT: f64 = 26.0;
+const TAB_PADDING: f64 = 10.0;
+const TAB_GAP: f64 = 2.0;
+const CLOSE_SIZE: f64 = 8.0;
+
+/// A row of document tabs: click to switch, × or middle-click to close, drag
+/// to reorder.
+pub struct TabStrip {
+    titles: Vec<String>,
+    layouts: Vec<TextLayout<String>>,
+    tabs: Vec<Rect>,
+    dragging: Option<usize>,
+}
+
+impl TabStrip {
+    pub fn new() -> Self {
+        Self {
+            titles: Vec::new(),
+            layouts: Vec::new(),
+            tabs: Vec::new(),
+            dragging: None,
+        }
+    }
+
+    fn title(doc: &Document) -> String {
+        let marker = match (doc.disk_conflict, doc.is_dirty()) {
+            (true, _) => "! ",
+            (false, true) => "\u{2022} ",
+            (false, false) => "",
+        };
+        format!("{marker}{}", doc.display_name())
+    }
+
+    /// Returns `true` when any tab title changed.
+    fn refresh_titles(&mut self, data: &AppState) -> bool {
+        let titles: Vec<String> = data.documents.iter().map(Self::title).collect();
+        if titles == self.titles {
+            return false;
+        }
+        self.layouts = titles
+            .iter()
+            .map(|title| TextLayout::from_text(title.clone()))
+            .collect();
+        self.titles = titles;
+        true
+    }
+
+    fn hit(&self, pos: Point) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.contains(pos))
+    }
+
+    fn close_rect(tab: Rect) -> Rect {
+        let center = Point::new(tab.x1 - TAB_PADDING - CLOSE_SIZE / 2.0, tab.center().y);
+        Rect::from_center_size(center, (CLOSE_SIZE + 6.0, CLOSE_SIZE + 6.0))
+    }
+}
+
+impl Widget<AppState> for TabStrip {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => {
+                let Some(index) = self.hit(mouse.pos) else {
+                    return;
+                };
+                data.active = index;
+                let close_clicked = Self::close_rect(self.tabs[index]).contains(mouse.pos);
+                if mouse.button == MouseButton::Middle
+                    || (mouse.button == MouseButton::Left && close_clicked)
+                {
+                    ctx.submit_command(CMD_CLOSE_TAB);
+                } else if mouse.button == MouseButton::Left {
+                    self.dragging = Some(index);
+                    ctx.set_active(true);
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) => {
+                if let (true, Some(from)) = (ctx.is_active(), self.dragging)
+                    && let Some(to) = self.hit(mouse.pos).filter(|&to| to != from)
+                {
+                    let doc = data.documents.remove(from);
+                    data.documents.insert(to, doc);
+                    data.active = to;
+                    self.dragging = Some(to);
+                }
+                ctx.request_paint();
+            }
+            Event::MouseUp(_) => {
+                self.dragging = None;
+                ctx.set_active(false);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        _env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.refresh_titles(data);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, _env: &Env) {
+        if self.refresh_titles(data) {
+            ctx.request_layout();
+        } else if old_data.active != data.active {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &AppState,
+        env: &Env,
+    ) -> Size {
+        self.tabs.clear();
+        let mut x = 0.0;
+        for layout in &mut self.layouts {
+            layout.rebuild_if_needed(ctx.text(), env);
+            let width = TAB_PADDING * 3.0 + layout.size().width + CLOSE_SIZE;
+            self.tabs.push(Rect::new(x, 0.0, x + width, TAB_HEIGHT));
+            x += width + TAB_GAP;
+        }
+        bc.constrain((bc.max().width, TAB_HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        let size = ctx.size();
+        ctx.clip(size.to_rect());
+        let text_color = env.get(theme::TEXT_COLOR);
+        for (index, (tab, layout)) in self.tabs.iter().zip(&self.layouts).enumerate() {
+            let fill = if index == data.active {
+                env.get(theme::BACKGROUND_LIGHT)
+            } else {
+                env.get(theme::BUTTON_DARK)
+            };
+            ctx.fill(*tab, &fill);
+            ctx.stroke(*tab, &env.get(theme::BORDER_DARK), 1.0);
+            let text_y = tab.center().y - layout.size().height / 2.0;
+            layout.draw(ctx, (tab.x0 + TAB_PADDING, text_y));
+
+            let close =
+                Rect::from_center_size(Self::close_rect(*tab).center(), (CLOSE_SIZE, CLOSE_SIZE));
+            ctx.stroke(
+                Line::new((close.x0, close.y0), (close.x1, close.y1)),
+                &text_color,
+                1.2,
+            );
+            ctx.stroke(
+                Line::new((close.x0, close.y1), (close.x1, close.y0)),
+                &text_color,
+                1.2,
+            );
+        }
+        let baseline = Line::new((0.0, size.height - 0.5), (size.width, size.height - 0.5));
+        ctx.stroke(baseline, &env.get(theme::BORDER_DARK), 1.0);
+    }
+}
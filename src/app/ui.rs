@@ -1,56 +1,106 @@
 use druid::widget::{Either, Flex, Label, ViewSwitcher};
-use druid::{Color, Widget, WidgetExt};
+use druid::{Widget, WidgetExt, theme};
 
 use super::commands::EDITOR_ID;
 use super::controller::AppController;
+use super::font_picker;
+use super::palette;
+use super::prompt;
 use super::search_panel;
-use super::state::AppState;
-use crate::editor::{EDITOR_FONT_KEY, EditorWidget};
+use super::state::{ActiveDocument, AppState};
+use super::tabs::TabStrip;
+use crate::cli::LaunchOptions;
+use crate::editor::{
+    EDITOR_FONT_KEY, EditorWidget, HIGHLIGHT_CURRENT_LINE, INDENT_WIDTH, INDENT_WITH_TABS, RULERS,
+    SHOW_LINE_NUMBERS, SHOW_WHITESPACE, TAB_WIDTH, WRAP_COLUMN,
+};
+use crate::theme::{BANNER_BACKGROUND, BANNER_TEXT, STATUS_BAR_BACKGROUND, STATUS_BAR_TEXT};
 
-pub fn build_root() -> impl Widget<AppState> {
+pub fn build_root(launch: LaunchOptions) -> impl Widget<AppState> {
+    // Rebuilt per tab so each document gets a fresh text box with its own caret.
     let editor_switcher = ViewSwitcher::new(
-        |data: &AppState, _| data.word_wrap,
-        |wrap, _data, _env| {
-            Box::new(EditorWidget::new(*wrap).with_id(EDITOR_ID).env_scope(
-                |env, data: &AppState| {
-                    env.set(EDITOR_FONT_KEY, data.font_descriptor());
-                },
-            ))
+        |data: &AppState, _| (data.word_wrap || data.wrap_at_column, data.doc().id),
+        |(wrap, _), _data, _env| {
+            Box::new(
+                EditorWidget::new(*wrap)
+                    .with_id(EDITOR_ID)
+                    .lens(ActiveDocument)
+                    .env_scope(|env, data: &AppState| {
+                        env.set(EDITOR_FONT_KEY, data.font_descriptor());
+                        env.set(SHOW_LINE_NUMBERS, data.show_line_numbers);
+                        env.set(HIGHLIGHT_CURRENT_LINE, data.highlight_current_line);
+                        env.set(SHOW_WHITESPACE, data.show_whitespace);
+                        env.set(RULERS, data.rulers.clone());
+                        let wrap_column = if data.wrap_at_column {
+                            u64::from(data.wrap_column)
+                        } else {
+                            0
+                        };
+                        env.set(WRAP_COLUMN, wrap_column);
+                        let indentation = data.indentation();
+                        env.set(INDENT_WITH_TABS, indentation.tabs);
+                        env.set(INDENT_WIDTH, indentation.width as u64);
+                        env.set(TAB_WIDTH, u64::from(data.tab_width));
+                    }),
+            )
         },
     )
     .expand();
 
     let status_bar = Flex::row()
         .with_child(Label::dynamic(|data: &AppState, _| {
-            format!("Ln {}, Col {}", data.metrics.line, data.metrics.column)
+            let metrics = &data.doc().metrics;
+            format!("Ln {}, Col {}", metrics.line, metrics.column)
         }))
         .with_spacer(12.0)
         .with_child(Label::dynamic(|data: &AppState, _| {
-            format!("Sel {}", data.metrics.selection)
+            format!("Sel {}", data.doc().metrics.selection)
         }))
         .with_flex_spacer(1.0)
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            let indentation = data.indentation();
+            if indentation.tabs {
+                format!("Tab Size: {}", indentation.width)
+            } else {
+                format!("Spaces: {}", indentation.width)
+            }
+        }))
+        .with_spacer(12.0)
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            format!("{}%", data.zoom_percent)
+        }))
+        .with_spacer(12.0)
         .with_child(Label::new("UTF-8"))
         .padding((6.0, 2.0))
-        .border(Color::grey(0.7), 1.0);
+        .background(STATUS_BAR_BACKGROUND)
+        .border(theme::BORDER_DARK, 1.0)
+        .env_scope(|env, _| env.set(theme::TEXT_COLOR, env.get(STATUS_BAR_TEXT)));
 
     let message = Either::new(
         |data: &AppState, _| data.info_message.is_some(),
         Label::dynamic(|data: &AppState, _| data.info_message.clone().unwrap_or_default())
+            .with_text_color(BANNER_TEXT)
             .padding((6.0, 2.0))
-            .background(Color::rgb8(0xFF, 0xF3, 0xC0))
-            .border(Color::grey(0.6), 1.0),
+            .background(BANNER_BACKGROUND)
+            .border(theme::BORDER_DARK, 1.0),
         Label::new(""),
     );
 
     Flex::column()
         .with_child(message)
+        .with_child(palette::build_palette())
+        .with_child(font_picker::build_font_picker())
+        .with_child(prompt::build_prompt_panel())
         .with_child(search_panel::build_search_panel())
+        .with_child(TabStrip::new())
         .with_flex_child(editor_switcher, 1.0)
         .with_child(Either::new(
-            |data: &AppState, _| data.show_status_bar,
+            |data: &AppState, _| data.status_bar_visible(),
             status_bar,
             Label::new(""),
         ))
         .padding(6.0)
-        .controller(AppController)
+        .background(theme::WINDOW_BACKGROUND_COLOR)
+        .env_scope(|env, data: &AppState| data.theme.apply(env))
+        .controller(AppController::new(launch))
 }
@@ -0,0 +1,98 @@
+use std::ops::Range;
+
+const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+/// How far to look for a partner before giving up, in bytes.
+const MAX_SCAN: usize = 256 * 1024;
+
+/// The bracket or quote touching `caret` (the one after it first) and its
+/// partner, as byte offsets of the two characters. Brackets inside
+/// `literals` (sorted string and comment ranges) only pair with each other.
+pub fn pair_at(text: &str, caret: usize, literals: &[Range<usize>]) -> Option<(usize, usize)> {
+    let after = (caret < text.len()).then_some(caret);
+    let before = text[..caret]
+        .chars()
+        .next_back()
+        .map(|ch| caret - ch.len_utf8());
+    [after, before]
+        .into_iter()
+        .flatten()
+        .find_map(|offset| partner(text, offset, literals).map(|other| (offset, other)))
+}
+
+fn partner(text: &str, offset: usize, literals: &[Range<usize>]) -> Option<usize> {
+    let ch = text[offset..].chars().next()?;
+    if QUOTES.contains(&ch) {
+        return quote_partner(text, offset, ch);
+    }
+    let literal = is_literal(literals, offset);
+    let counts = |index: usize| is_literal(literals, index) == literal;
+    if let Some(&(open, close)) = PAIRS.iter().find(|(open, _)| *open == ch) {
+        let limit = (offset + MAX_SCAN).min(text.len());
+        let mut depth = 0;
+        for (index, ch) in text[offset..limit].char_indices() {
+            let index = offset + index;
+            if !counts(index) {
+                continue;
+            }
+            if ch == open {
+                depth += 1;
+            } else if ch == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+        }
+        return None;
+    }
+    if let Some(&(open, close)) = PAIRS.iter().find(|(_, close)| *close == ch) {
+        let limit = offset.saturating_sub(MAX_SCAN);
+        let mut depth = 0;
+        for (index, ch) in text[..offset + ch.len_utf8()].char_indices().rev() {
+            if index < limit {
+                break;
+            }
+            if !counts(index) {
+                continue;
+            }
+            if ch == close {
+                depth += 1;
+            } else if ch == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Quotes pair up in order along their line, skipping escaped ones.
+fn quote_partner(text: &str, offset: usize, quote: char) -> Option<usize> {
+    let start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let end = text[offset..]
+        .find('\n')
+        .map_or(text.len(), |index| offset + index);
+    let line = &text[start..end];
+    let quotes: Vec<usize> = line
+        .char_indices()
+        .filter(|&(index, ch)| ch == quote && !line[..index].ends_with('\\'))
+        .map(|(index, _)| start + index)
+        .collect();
+    let position = quotes.iter().position(|&index| index == offset)?;
+    let other = if position % 2 == 0 {
+        position + 1
+    } else {
+        position - 1
+    };
+    quotes.get(other).copied()
+}
+
+fn is_literal(literals: &[Range<usize>], offset: usize) -> bool {
+    let index = literals.partition_point(|range| range.end <= offset);
+    literals
+        .get(index)
+        .is_some_and(|range| range.start <= offset)
+}
@@ -0,0 +1,157 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+    Sentence,
+    Snake,
+    Camel,
+    Kebab,
+    Invert,
+}
+
+/// Converts `text` to `case` with full Unicode case mapping, so a character
+/// may map to several (`ß` to `SS`). The identifier styles work line by line,
+/// keeping each line's surrounding whitespace.
+pub fn convert(text: &str, case: Case) -> String {
+    match case {
+        Case::Upper => text.to_uppercase(),
+        Case::Lower => text.to_lowercase(),
+        Case::Title => title(text),
+        Case::Sentence => sentence(text),
+        Case::Invert => text
+            .chars()
+            .map(|ch| {
+                if ch.is_uppercase() {
+                    ch.to_lowercase().collect::<String>()
+                } else {
+                    ch.to_uppercase().collect()
+                }
+            })
+            .collect(),
+        Case::Snake | Case::Camel | Case::Kebab => text
+            .split('\n')
+            .map(|line| {
+                let content = line.trim();
+                let start = line.len() - line.trim_start().len();
+                let words = words(content);
+                let joined = match case {
+                    Case::Snake => words.join("_"),
+                    Case::Kebab => words.join("-"),
+                    _ => words
+                        .iter()
+                        .enumerate()
+                        .map(|(index, word)| {
+                            if index == 0 {
+                                word.clone()
+                            } else {
+                                capitalize(word)
+                            }
+                        })
+                        .collect(),
+                };
+                format!(
+                    "{}{joined}{}",
+                    &line[..start],
+                    &line[start + content.len()..]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Capitalizes the first letter of every word and lowercases the rest. An
+/// apostrophe inside a word does not start a new one.
+fn title(text: &str) -> String {
+    // Lowercasing the whole text first lets context-sensitive mappings, such
+    // as a word-final sigma, see their neighbours.
+    let mut result = String::with_capacity(text.len());
+    let mut in_word = false;
+    for ch in text.to_lowercase().chars() {
+        if ch.is_alphanumeric() {
+            if in_word {
+                result.push(ch);
+            } else {
+                result.extend(ch.to_uppercase());
+            }
+            in_word = true;
+        } else {
+            in_word = in_word && is_apostrophe(ch);
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Lowercases everything, then capitalizes the first letter of the text and
+/// of each sentence after a `.`, `!` or `?`.
+fn sentence(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut capitalize_next = true;
+    for ch in text.to_lowercase().chars() {
+        if ch.is_alphanumeric() {
+            if capitalize_next {
+                result.extend(ch.to_uppercase());
+            } else {
+                result.push(ch);
+            }
+            capitalize_next = false;
+        } else {
+            if matches!(ch, '.' | '!' | '?') {
+                capitalize_next = true;
+            }
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Lowercased words of an identifier or phrase. Words break at anything that
+/// is not a letter or digit, where lowercase meets uppercase (`camelCase`),
+/// and before the last capital of an acronym (`HTTPServer`); apostrophes
+/// inside a word are dropped.
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (index, &ch) in chars.iter().enumerate() {
+        if is_apostrophe(ch) && !current.is_empty() {
+            continue;
+        }
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current).to_lowercase());
+            }
+            continue;
+        }
+        if index > 0 && !current.is_empty() {
+            let prev = chars[index - 1];
+            let next = chars.get(index + 1);
+            let boundary = ((prev.is_lowercase() || prev.is_numeric()) && ch.is_uppercase())
+                || (prev.is_uppercase()
+                    && ch.is_uppercase()
+                    && next.is_some_and(|next| next.is_lowercase()));
+            if boundary {
+                words.push(std::mem::take(&mut current).to_lowercase());
+            }
+        }
+        current.push(ch);
+    }
+    if !current.is_empty() {
+        words.push(current.to_lowercase());
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_apostrophe(ch: char) -> bool {
+    ch == '\'' || ch == '\u{2019}'
+}
@@ -0,0 +1,110 @@
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub const USAGE: &str = "\
+Usage: rustpad [OPTIONS] [FILE[:LINE[:COLUMN]]]...
+
+Arguments:
+  FILE               Open FILE, optionally placing the caret at LINE and COLUMN
+  -                  Read standard input into an untitled document
+
+Options:
+      --new-file     Open missing files as empty documents, created on first save
+  -h, --help         Print this help";
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("unknown option '{0}'")]
+    UnknownOption(String),
+    #[error("standard input ('-') can only be given once")]
+    RepeatedStdin,
+    #[error("unable to read standard input: {0}")]
+    Stdin(#[from] io::Error),
+}
+
+pub enum Invocation {
+    Launch(LaunchOptions),
+    Help,
+}
+
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    pub files: Vec<FileArg>,
+    pub read_stdin: bool,
+    pub stdin_text: Option<String>,
+    pub create_missing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileArg {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl LaunchOptions {
+    pub fn load_stdin(&mut self) -> Result<(), CliError> {
+        if self.read_stdin {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            self.stdin_text = Some(text);
+        }
+        Ok(())
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Invocation, CliError> {
+    let mut options = LaunchOptions::default();
+    let mut only_files = false;
+    for arg in args {
+        if only_files {
+            options.files.push(parse_file_arg(&arg));
+            continue;
+        }
+        match arg.as_str() {
+            "--" => only_files = true,
+            "-h" | "--help" => return Ok(Invocation::Help),
+            "--new-file" => options.create_missing = true,
+            "-" if options.read_stdin => return Err(CliError::RepeatedStdin),
+            "-" => options.read_stdin = true,
+            flag if flag.starts_with('-') => return Err(CliError::UnknownOption(arg)),
+            _ => options.files.push(parse_file_arg(&arg)),
+        }
+    }
+    Ok(Invocation::Launch(options))
+}
+
+/// Splits a trailing `:LINE` or `:LINE:COLUMN` off a path. A path that exists
+/// as written wins, so file names that really contain colons still open.
+fn parse_file_arg(arg: &str) -> FileArg {
+    let plain = FileArg {
+        path: PathBuf::from(arg),
+        line: None,
+        column: None,
+    };
+    if plain.path.exists() {
+        return plain;
+    }
+    let mut parts = arg.rsplitn(3, ':');
+    let last = parts.next().and_then(|part| part.parse::<usize>().ok());
+    let middle = parts.next();
+    let rest = parts.next();
+    match (last, middle, rest) {
+        (Some(column), Some(middle), Some(path)) if middle.parse::<usize>().is_ok() => FileArg {
+            path: PathBuf::from(path),
+            line: middle.parse().ok(),
+            column: Some(column),
+        },
+        (Some(line), Some(_), _) => {
+            let (path, _) = arg.rsplit_once(':').unwrap_or((arg, ""));
+            FileArg {
+                path: PathBuf::from(path),
+                line: Some(line),
+                column: None,
+            }
+        }
+        _ => plain,
+    }
+}
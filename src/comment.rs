@@ -0,0 +1,150 @@
+use std::ops::Range;
+
+use crate::lines::{self, Edit};
+use crate::syntax::Language;
+
+/// How a language writes comments.
+#[derive(Clone, Copy, Debug)]
+pub struct CommentSyntax {
+    pub line: Option<&'static str>,
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+impl CommentSyntax {
+    /// `None` for languages without comments, such as JSON and plain text.
+    pub fn for_language(language: Language) -> Option<Self> {
+        let (line, block) = match language {
+            Language::Rust => (Some("//"), Some(("/*", "*/"))),
+            Language::Shell | Language::Toml | Language::Python => (Some("#"), None),
+            Language::Markdown => (None, Some(("<!--", "-->"))),
+            Language::Json | Language::Plain => return None,
+        };
+        Some(Self { line, block })
+    }
+}
+
+/// Comments out the non-blank lines the selection touches, each at the
+/// smallest indentation among them, or uncomments them when they all already
+/// are. Languages without line comments wrap each line in a block comment.
+pub fn toggle_line(text: &str, selection: Range<usize>, syntax: CommentSyntax) -> Option<Edit> {
+    let (open, close) = match (syntax.line, syntax.block) {
+        (Some(token), _) => (token, None),
+        (None, Some((open, close))) => (open, Some(close)),
+        (None, None) => return None,
+    };
+    let lines = content_lines(text, &selection);
+    if lines.is_empty() {
+        return None;
+    }
+    let commented = lines.iter().all(|line| {
+        let content = &text[line.content.clone()];
+        content.starts_with(open) && close.is_none_or(|close| content.ends_with(close))
+    });
+    let opening = format!("{open} ");
+    let closing = close.map(|close| format!(" {close}"));
+    let mut changes = Vec::new();
+    if commented {
+        for line in &lines {
+            changes.extend(unwrap(text, line.content.clone(), open, close));
+        }
+    } else {
+        let indent = lines
+            .iter()
+            .map(|line| line.content.start - line.start)
+            .min()
+            .unwrap_or(0);
+        for line in &lines {
+            let at = line.start + indent;
+            changes.push((at..at, opening.as_str()));
+            if let Some(closing) = &closing {
+                changes.push((line.content.end..line.content.end, closing.as_str()));
+            }
+        }
+    }
+    Some(lines::splice(text, selection, &changes))
+}
+
+/// Wraps the lines the selection touches in one block comment, opened at the
+/// smallest indentation, or unwraps them when they already are. Languages
+/// without block comments get line comments instead.
+pub fn toggle_block(text: &str, selection: Range<usize>, syntax: CommentSyntax) -> Option<Edit> {
+    let Some((open, close)) = syntax.block else {
+        return toggle_line(text, selection, syntax);
+    };
+    let lines = content_lines(text, &selection);
+    let (first, last) = (lines.first()?, lines.last()?);
+    let opening = format!("{open} ");
+    let closing = format!(" {close}");
+    let changes = if text[first.content.clone()].starts_with(open)
+        && text[last.content.clone()].ends_with(close)
+    {
+        unwrap(
+            text,
+            first.content.start..last.content.end,
+            open,
+            Some(close),
+        )
+    } else {
+        let indent = lines
+            .iter()
+            .map(|line| line.content.start - line.start)
+            .min()
+            .unwrap_or(0);
+        let at = first.start + indent;
+        vec![
+            (at..at, opening.as_str()),
+            (last.content.end..last.content.end, closing.as_str()),
+        ]
+    };
+    Some(lines::splice(text, selection, &changes))
+}
+
+/// The deletions that take `open`, `close` and a space inside each off the
+/// comment spanning `range`.
+fn unwrap(
+    text: &str,
+    range: Range<usize>,
+    open: &str,
+    close: Option<&str>,
+) -> Vec<(Range<usize>, &'static str)> {
+    let after_open = range.start + open.len();
+    let after_open = (after_open + usize::from(text[after_open..].starts_with(' '))).min(range.end);
+    let Some(close) = close else {
+        return vec![(range.start..after_open, "")];
+    };
+    let before_close = range.end - close.len();
+    let before_close = before_close - usize::from(text[..before_close].ends_with(' '));
+    if before_close < after_open {
+        // Nothing but the comment markers.
+        return vec![(range, "")];
+    }
+    vec![(range.start..after_open, ""), (before_close..range.end, "")]
+}
+
+struct ContentLine {
+    start: usize,
+    /// The line without its indentation and trailing whitespace.
+    content: Range<usize>,
+}
+
+/// The non-blank lines the selection touches.
+fn content_lines(text: &str, selection: &Range<usize>) -> Vec<ContentLine> {
+    lines::touched_line_starts(text, selection)
+        .into_iter()
+        .filter_map(|start| {
+            let end = text[start..]
+                .find('\n')
+                .map_or(text.len(), |index| start + index);
+            let line = &text[start..end];
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                return None;
+            }
+            let content_start = start + line.len() - line.trim_start().len();
+            Some(ContentLine {
+                start,
+                content: content_start..content_start + trimmed.len(),
+            })
+        })
+        .collect()
+}
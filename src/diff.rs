@@ -0,0 +1,127 @@
+/// Upper bound on the LCS table size; larger inputs fall back to a block replace.
+const MAX_TABLE_CELLS: usize = 4_000_000;
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<LineChange<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_lines[prefix..old_lines.len() - suffix];
+    let new_mid = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut changes: Vec<LineChange> = old_lines[..prefix]
+        .iter()
+        .map(|line| LineChange::Same(line))
+        .collect();
+    changes.extend(diff_middle(old_mid, new_mid));
+    changes.extend(
+        old_lines[old_lines.len() - suffix..]
+            .iter()
+            .map(|line| LineChange::Same(line)),
+    );
+    changes
+}
+
+/// Renders a compact diff with a little context around each change, in the
+/// familiar `-`/`+` line-prefix style. Returns `None` when both sides match.
+pub fn render_diff(old: &str, new: &str) -> Option<String> {
+    let changes = diff_lines(old, new);
+    if changes
+        .iter()
+        .all(|change| matches!(change, LineChange::Same(_)))
+    {
+        return None;
+    }
+    let near_change = |index: usize| {
+        let lo = index.saturating_sub(CONTEXT_LINES);
+        let hi = (index + CONTEXT_LINES + 1).min(changes.len());
+        changes[lo..hi]
+            .iter()
+            .any(|change| !matches!(change, LineChange::Same(_)))
+    };
+    let mut out = String::new();
+    let mut skipped = false;
+    for (index, change) in changes.iter().enumerate() {
+        match change {
+            LineChange::Same(line) if near_change(index) => {
+                out.push_str("  ");
+                out.push_str(line);
+            }
+            LineChange::Same(_) => {
+                if !skipped {
+                    out.push_str("  ...\n");
+                }
+                skipped = true;
+                continue;
+            }
+            LineChange::Removed(line) => {
+                out.push_str("- ");
+                out.push_str(line);
+            }
+            LineChange::Added(line) => {
+                out.push_str("+ ");
+                out.push_str(line);
+            }
+        }
+        out.push('\n');
+        skipped = false;
+    }
+    Some(out)
+}
+
+fn diff_middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<LineChange<'a>> {
+    if old.len().saturating_mul(new.len()) > MAX_TABLE_CELLS {
+        return old
+            .iter()
+            .map(|line| LineChange::Removed(line))
+            .chain(new.iter().map(|line| LineChange::Added(line)))
+            .collect();
+    }
+    let width = new.len() + 1;
+    let mut table = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = if old[i] == new[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+    let mut changes = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(LineChange::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            changes.push(LineChange::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Added(new[j]));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().map(|line| LineChange::Removed(line)));
+    changes.extend(new[j..].iter().map(|line| LineChange::Added(line)));
+    changes
+}
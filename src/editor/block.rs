@@ -0,0 +1,138 @@
+use super::ByteRange;
+
+/// A rectangular selection, as (line, column) corners counted from zero in
+/// characters. Columns may lie past the end of short lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub anchor: (usize, usize),
+    pub active: (usize, usize),
+}
+
+impl Block {
+    pub fn at(line: usize, column: usize) -> Self {
+        Self {
+            anchor: (line, column),
+            active: (line, column),
+        }
+    }
+
+    pub fn first_line(&self) -> usize {
+        self.anchor.0.min(self.active.0)
+    }
+
+    pub fn last_line(&self) -> usize {
+        self.anchor.0.max(self.active.0)
+    }
+
+    pub fn left(&self) -> usize {
+        self.anchor.1.min(self.active.1)
+    }
+
+    pub fn right(&self) -> usize {
+        self.anchor.1.max(self.active.1)
+    }
+
+    /// The same lines with both edges at `column`.
+    pub fn collapsed(&self, column: usize) -> Self {
+        Self {
+            anchor: (self.anchor.0, column),
+            active: (self.active.0, column),
+        }
+    }
+
+    /// The selected part of each line, top to bottom, cut short where a line
+    /// ends inside the block.
+    pub fn ranges(&self, text: &str) -> Vec<ByteRange> {
+        let starts = line_starts(text);
+        (self.first_line()..=self.last_line().min(starts.len() - 1))
+            .map(|line| {
+                let content = line_text(text, &starts, line);
+                let base = starts[line];
+                ByteRange {
+                    start: base + column_offset(content, self.left()),
+                    end: base + column_offset(content, self.right()),
+                }
+            })
+            .collect()
+    }
+
+    /// The selected text, one line per row.
+    pub fn copy(&self, text: &str) -> String {
+        let rows: Vec<&str> = self
+            .ranges(text)
+            .into_iter()
+            .map(|range| &text[range.start..range.end])
+            .collect();
+        rows.join("\n")
+    }
+}
+
+/// Replaces columns `left..right` of consecutive lines from `first_line`
+/// with `rows`, one per line. With `pad`, short lines are filled out with
+/// spaces (and missing lines added) so every row starts at `left`;
+/// without it, lines ending before `left` are left alone.
+pub fn fill(
+    text: &str,
+    first_line: usize,
+    left: usize,
+    right: usize,
+    rows: &[&str],
+    pad: bool,
+) -> String {
+    let mut starts = line_starts(text);
+    let mut text = text.to_string();
+    for (index, row) in rows.iter().enumerate() {
+        let line = first_line + index;
+        if line >= starts.len() {
+            if !pad {
+                break;
+            }
+            text.push('\n');
+            starts.push(text.len());
+        }
+        let base = starts[line];
+        let content = line_text(&text, &starts, line);
+        let length = content.chars().count();
+        if length < left && !pad {
+            continue;
+        }
+        let start = column_offset(content, left);
+        let end = column_offset(content, right);
+        let padding = " ".repeat(left.saturating_sub(length));
+        let replacement = format!("{padding}{row}");
+        let delta = replacement.len() as isize - (end - start) as isize;
+        text.replace_range(base + start..base + end, &replacement);
+        for later in &mut starts[line + 1..] {
+            *later = later.saturating_add_signed(delta);
+        }
+    }
+    text
+}
+
+/// The zero-based line and character column of `offset`.
+pub fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (
+        before.matches('\n').count(),
+        before[line_start..].chars().count(),
+    )
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+fn line_text<'a>(text: &'a str, starts: &[usize], line: usize) -> &'a str {
+    let end = starts.get(line + 1).map_or(text.len(), |&next| next - 1);
+    &text[starts[line]..end]
+}
+
+/// Byte offset of `column` within a line, or its end when it is shorter.
+fn column_offset(line: &str, column: usize) -> usize {
+    line.char_indices()
+        .nth(column)
+        .map_or(line.len(), |(index, _)| index)
+}
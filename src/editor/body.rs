@@ -0,0 +1,1101 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use druid::commands::SCROLL_TO_VIEW;
+use druid::keyboard_types::Key;
+use druid::kurbo::{Point, Rect};
+use druid::piet::{PietTextLayout, TextLayout as _};
+use druid::text::{ImeInvalidation, Selection};
+use druid::widget::TextBox;
+use druid::widget::prelude::*;
+use druid::{Application, Color, KeyEvent, KeyOrValue, WidgetPod, commands, theme};
+
+use crate::app::document::Document;
+use crate::brackets;
+use crate::folding::{self, FoldRegion, Folds};
+use crate::indent::{self, Indentation};
+use crate::keymap;
+use crate::lines;
+use crate::syntax::{Highlighter, Language};
+
+use super::block::{self, Block};
+use super::carets::{self, Edit, Motion};
+use super::text::EditorText;
+use super::{
+    ADD_CARET_ABOVE, ADD_CARET_BELOW, APPLY_SELECTION, ByteRange, CharRange, EDITOR_FONT_KEY,
+    FOLD_ALL, HIGHLIGHT_CURRENT_LINE, INDENT_WIDTH, INDENT_WITH_TABS, RULERS, SHOW_WHITESPACE,
+    SelectionState, TAB_WIDTH, TOGGLE_FOLD, UNFOLD_ALL, WRAP_COLUMN, column_from_bytes,
+    count_chars, line_from_bytes,
+};
+use super::{highlight, overlay};
+
+#[derive(Clone, Copy, Debug)]
+pub struct VisualLine {
+    /// Byte offset of the first character on this line.
+    pub start: usize,
+    pub y: f64,
+    pub height: f64,
+    pub baseline: f64,
+}
+
+/// The scrolled content of the editor. Wraps a multiline text box that is
+/// always laid out at its full height, so its own scrolling never kicks in.
+pub struct EditorBody {
+    textbox: WidgetPod<EditorText, TextBox<EditorText>>,
+    /// The document text as the text box sees it, with the folded ranges.
+    /// Edits made in the box are copied back to the document.
+    content: EditorText,
+    wrap: bool,
+    column_width: f64,
+    highlighter: Highlighter,
+    /// The text laid out with token colors, drawn over the text box's own
+    /// (transparent) text. Rebuilt after edits and theme or font changes.
+    styled: Option<PietTextLayout>,
+    styled_width: f64,
+    /// Strings and comments, which brackets outside them skip over.
+    literals: Vec<Range<usize>>,
+    /// Foldable blocks, worked out again on the first layout after an edit.
+    regions: Option<Vec<FoldRegion>>,
+    /// The folds shown in `content`, copied to the document so they outlast
+    /// the widget.
+    folds: Folds,
+    /// The Alt+drag selection the carets were made from, while they last.
+    block: Option<Block>,
+    block_dragging: bool,
+    /// The last block copied, so pasting it back keeps its shape.
+    block_clipboard: Option<String>,
+}
+
+impl EditorBody {
+    pub fn new(wrap: bool) -> Self {
+        let textbox = TextBox::multiline()
+            .with_font(KeyOrValue::Key(EDITOR_FONT_KEY))
+            .with_line_wrapping(wrap);
+        Self {
+            textbox: WidgetPod::new(textbox),
+            content: EditorText::default(),
+            wrap,
+            column_width: 0.0,
+            highlighter: Highlighter::new(Language::Plain, ""),
+            styled: None,
+            styled_width: 0.0,
+            literals: Vec::new(),
+            regions: None,
+            folds: Folds::default(),
+            block: None,
+            block_dragging: false,
+            block_clipboard: None,
+        }
+    }
+
+    pub fn set_selection(&mut self, range: ByteRange) -> Option<ImeInvalidation> {
+        self.textbox
+            .widget_mut()
+            .text_mut()
+            .borrow_mut()
+            .set_selection(Selection::new(range.start, range.end))
+    }
+
+    pub fn apply_revision(&mut self, ctx: &mut EventCtx, data: &mut Document, undo: bool) {
+        let revision = if undo {
+            data.history.undo(&data.text, data.selection.byte_range)
+        } else {
+            data.history.redo(&data.text, data.selection.byte_range)
+        };
+        if let Some(revision) = revision {
+            data.text = revision.text.to_string();
+            data.selection = SelectionState::from_bytes(&data.text, revision.selection);
+            if let Some(inval) = self.set_selection(data.selection.byte_range) {
+                ctx.invalidate_text_input(inval);
+            }
+            ctx.request_layout();
+        }
+    }
+
+    pub fn fold_regions(&self) -> &[FoldRegion] {
+        self.regions.as_deref().unwrap_or_default()
+    }
+
+    pub fn folds(&self) -> &[Range<usize>] {
+        &self.content.folds
+    }
+
+    /// Hands the text box and the document a new set of folds; the box picks
+    /// them up in `update`.
+    fn set_folds(&mut self, ctx: &mut EventCtx, data: &mut Document, folds: Folds) {
+        self.content.folds = folds.ranges.clone();
+        data.folds = folds.clone();
+        self.folds = folds;
+        self.styled = None;
+        ctx.request_update();
+        ctx.request_layout();
+    }
+
+    /// Opens every fold that hides either end of `range`.
+    fn unfold(&mut self, ctx: &mut EventCtx, data: &mut Document, range: ByteRange) {
+        let hides =
+            |fold: &Range<usize>| is_hidden(fold, range.start) || is_hidden(fold, range.end);
+        if self.folds.ranges.iter().any(hides) {
+            let folds = self.folds.filter(|fold| !hides(fold));
+            self.set_folds(ctx, data, folds);
+        }
+    }
+
+    /// Folds or unfolds the block whose first line starts at `line`.
+    fn toggle_fold(&mut self, ctx: &mut EventCtx, data: &mut Document, line: usize) {
+        let Some(region) = self
+            .fold_regions()
+            .iter()
+            .find(|region| region.line == line)
+        else {
+            return;
+        };
+        let hidden = region.hidden.clone();
+        let mut folds = self.folds.ranges.to_vec();
+        if let Some(index) = folds.iter().position(|fold| *fold == hidden) {
+            folds.remove(index);
+        } else {
+            folds.push(hidden);
+        }
+        self.set_folds(ctx, data, Folds::new(folds, &data.text));
+        self.move_caret_out_of_folds(ctx, data);
+    }
+
+    /// A caret left inside a fold would vanish, so it moves to the end of the
+    /// outermost fold's first line.
+    fn move_caret_out_of_folds(&mut self, ctx: &mut EventCtx, data: &mut Document) {
+        let Some(caret) = self.caret() else {
+            return;
+        };
+        let Some(start) = self
+            .content
+            .folds
+            .iter()
+            .filter(|fold| is_hidden(fold, caret))
+            .map(|fold| fold.start)
+            .min()
+        else {
+            return;
+        };
+        if let Some(inval) = self.set_selection(ByteRange { start, end: start }) {
+            ctx.invalidate_text_input(inval);
+        }
+        self.update_selection(data);
+    }
+
+    /// Every caret, the text box's own included, sorted and merged, with the
+    /// index of the text box's one.
+    fn carets(&self, data: &Document) -> Option<(Vec<ByteRange>, usize)> {
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return None;
+        }
+        let selection = component.borrow().selection();
+        let primary = ByteRange {
+            start: selection.min(),
+            end: selection.max(),
+        };
+        // Extra carets can be left behind by an edit made elsewhere.
+        let text = &data.text;
+        let extra: Vec<ByteRange> = data
+            .selection
+            .extra
+            .iter()
+            .filter(|range| {
+                range.start <= range.end
+                    && text.is_char_boundary(range.start)
+                    && text.is_char_boundary(range.end)
+            })
+            .copied()
+            .collect();
+        Some(carets::normalize(primary, &extra))
+    }
+
+    fn set_carets(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut Document,
+        carets: Vec<ByteRange>,
+        primary: usize,
+    ) {
+        if let Some(inval) = self.set_selection(carets[primary]) {
+            ctx.invalidate_text_input(inval);
+        }
+        let extra = carets
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, _)| index != primary)
+            .map(|(_, range)| range);
+        data.selection.extra = Arc::new(extra.collect());
+        self.update_selection(data);
+        ctx.request_paint();
+    }
+
+    fn clear_extra_carets(&mut self, ctx: &mut EventCtx, data: &mut Document) {
+        self.block = None;
+        if !data.selection.extra.is_empty() {
+            data.selection.extra = Arc::default();
+            ctx.request_paint();
+        }
+    }
+
+    /// Makes the same edit at every caret, as one typing step.
+    fn edit_carets(&mut self, ctx: &mut EventCtx, data: &mut Document, edit: &Edit) {
+        let Some((carets, primary)) = self.carets(data) else {
+            return;
+        };
+        let (text, moved) = carets::apply(&data.text, &carets, edit);
+        data.history
+            .record_typing(&data.text, data.selection.byte_range);
+        // The text box sees the new text before its selection is moved into it.
+        self.content.text = text.clone();
+        data.text = text;
+        ctx.invalidate_text_input(ImeInvalidation::Reset);
+        // Carets that deleted their way into each other become one.
+        let (moved, primary) = carets::normalize(moved[primary], &moved);
+        self.set_carets(ctx, data, moved, primary);
+    }
+
+    /// Adds a caret on the line above the topmost caret, or below the last.
+    fn add_caret(&mut self, ctx: &mut EventCtx, data: &mut Document, above: bool) {
+        let Some((mut carets, primary)) = self.carets(data) else {
+            return;
+        };
+        let from = if above {
+            carets[0].end
+        } else {
+            carets[carets.len() - 1].end
+        };
+        let Some(offset) = carets::adjacent_line(&data.text, from, above) else {
+            return;
+        };
+        let main = carets.remove(primary);
+        carets.push(ByteRange {
+            start: offset,
+            end: offset,
+        });
+        let (carets, primary) = carets::normalize(main, &carets);
+        self.set_carets(ctx, data, carets, primary);
+    }
+
+    /// The line and column under `pos`, counting columns past the end of a
+    /// short line as if it were padded with spaces.
+    fn block_position(&self, text: &str, pos: Point) -> Option<(usize, usize)> {
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return None;
+        }
+        let session = component.borrow();
+        let layout = session.layout.layout()?;
+        let point = pos - self.text_origin().to_vec2();
+        let hit = layout.hit_test_point(point);
+        let (line, column) = block::position(text, hit.idx);
+        let end_x = layout.hit_test_text_position(hit.idx).point.x;
+        let beyond = if point.x > end_x && self.column_width > 0.0 {
+            ((point.x - end_x) / self.column_width).round() as usize
+        } else {
+            0
+        };
+        Some((line, column + beyond))
+    }
+
+    /// Selects `block` as one caret range per line.
+    fn set_block(&mut self, ctx: &mut EventCtx, data: &mut Document, block: Block) {
+        let carets = block.ranges(&data.text);
+        if carets.is_empty() {
+            return;
+        }
+        self.block = Some(block);
+        let primary = block
+            .active
+            .0
+            .saturating_sub(block.first_line())
+            .min(carets.len() - 1);
+        self.set_carets(ctx, data, carets, primary);
+    }
+
+    /// The block, unless its carets have since been changed some other way.
+    fn current_block(&self, data: &Document) -> Option<Block> {
+        let block = self.block?;
+        let (carets, _) = self.carets(data)?;
+        (carets == block.ranges(&data.text)).then_some(block)
+    }
+
+    /// Replaces the block's columns on each of its lines with `rows` (or the
+    /// one row on every line) and leaves empty carets at `column`.
+    fn replace_block(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut Document,
+        area: Block,
+        rows: &[&str],
+        pad: bool,
+        column: usize,
+    ) {
+        let count = area.last_line() - area.first_line() + 1;
+        let rows = if rows.len() == 1 {
+            vec![rows[0]; count]
+        } else {
+            rows.to_vec()
+        };
+        let text = block::fill(
+            &data.text,
+            area.first_line(),
+            area.left(),
+            area.right(),
+            &rows,
+            pad,
+        );
+        if text == data.text {
+            return;
+        }
+        data.history
+            .record_typing(&data.text, data.selection.byte_range);
+        self.content.text = text.clone();
+        data.text = text;
+        ctx.invalidate_text_input(ImeInvalidation::Reset);
+        self.set_block(ctx, data, area.collapsed(column));
+    }
+
+    /// Typing and deleting within a block selection; other keys end it.
+    fn block_key(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut Document,
+        key: &KeyEvent,
+        indentation: Indentation,
+    ) -> bool {
+        let Some(block) = self.current_block(data) else {
+            return false;
+        };
+        let plain = !key.mods.ctrl() && !key.mods.meta();
+        let (left, right) = (block.left(), block.right());
+        match &key.key {
+            Key::Character(value) if plain => {
+                let column = left + value.chars().count();
+                self.replace_block(ctx, data, block, &[value], true, column);
+            }
+            Key::Tab if plain && !key.mods.shift() => {
+                let unit = indentation.unit();
+                let column = left + unit.chars().count();
+                self.replace_block(ctx, data, block, &[&unit], true, column);
+            }
+            Key::Backspace | Key::Delete if plain && left != right => {
+                self.replace_block(ctx, data, block, &[""], false, left);
+            }
+            Key::Backspace if plain => {
+                if left > 0 {
+                    let area = Block {
+                        anchor: (block.anchor.0, left - 1),
+                        active: (block.active.0, left),
+                    };
+                    self.replace_block(ctx, data, area, &[""], false, left - 1);
+                }
+            }
+            Key::Delete if plain => {
+                let area = Block {
+                    anchor: (block.anchor.0, left),
+                    active: (block.active.0, left + 1),
+                };
+                self.replace_block(ctx, data, area, &[""], false, left);
+            }
+            Key::Shift | Key::Control | Key::Alt | Key::Meta => return false,
+            _ => {
+                self.block = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Copies or cuts the block, one line per row.
+    fn copy_block(&mut self, ctx: &mut EventCtx, data: &mut Document, block: Block, cut: bool) {
+        let copied = block.copy(&data.text);
+        Application::global().clipboard().put_string(&copied);
+        self.block_clipboard = Some(copied);
+        if cut {
+            data.checkpoint();
+            self.replace_block(ctx, data, block, &[""], false, block.left());
+        }
+    }
+
+    /// Pastes a copied block as a block: into the selected one when it has as
+    /// many lines, or down from the caret's column otherwise.
+    fn paste_block(&mut self, ctx: &mut EventCtx, data: &mut Document, value: &str) -> bool {
+        if self.block_clipboard.as_deref() != Some(value) {
+            return false;
+        }
+        let rows: Vec<&str> = value.split('\n').collect();
+        let widest = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        data.checkpoint();
+        match self.current_block(data) {
+            Some(block) if block.last_line() - block.first_line() + 1 == rows.len() => {
+                self.replace_block(ctx, data, block, &rows, true, block.left() + widest);
+            }
+            _ => {
+                let Some((carets, primary)) = self.carets(data) else {
+                    return false;
+                };
+                let (line, column) = block::position(&data.text, carets[primary].start);
+                let area = Block {
+                    anchor: (line, column),
+                    active: (line + rows.len() - 1, column),
+                };
+                self.replace_block(ctx, data, area, &rows, true, column + widest);
+            }
+        }
+        true
+    }
+
+    /// Keys that act on every caret while there are several; returns `false`
+    /// for keys left to the text box, most of which drop the extra carets.
+    fn multi_caret_key(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut Document,
+        key: &KeyEvent,
+        indentation: Indentation,
+    ) -> bool {
+        let plain = !key.mods.ctrl() && !key.mods.meta();
+        let motion = match &key.key {
+            Key::ArrowLeft => Some(Motion::Left),
+            Key::ArrowRight => Some(Motion::Right),
+            Key::Home => Some(Motion::Home),
+            Key::End => Some(Motion::End),
+            _ => None,
+        };
+        match &key.key {
+            Key::Character(value) if plain => self.edit_carets(ctx, data, &Edit::Insert(value)),
+            Key::Enter if plain => self.edit_carets(ctx, data, &Edit::Insert("\n")),
+            Key::Tab if plain && !key.mods.shift() => {
+                self.edit_carets(ctx, data, &Edit::Insert(&indentation.unit()));
+            }
+            Key::Backspace if plain => self.edit_carets(ctx, data, &Edit::Backspace),
+            Key::Delete if plain => self.edit_carets(ctx, data, &Edit::Delete),
+            _ if plain && !key.mods.shift() && motion.is_some() => {
+                if let (Some(motion), Some((carets, primary))) = (motion, self.carets(data)) {
+                    let moved = carets::move_all(&data.text, &carets, &motion);
+                    // Carets that meet merge into one.
+                    let (carets, primary) = carets::normalize(moved[primary], &moved);
+                    self.set_carets(ctx, data, carets, primary);
+                }
+            }
+            Key::Escape => self.clear_extra_carets(ctx, data),
+            Key::Shift | Key::Control | Key::Alt | Key::Meta => return false,
+            _ => {
+                self.clear_extra_carets(ctx, data);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Tab indents (the selected lines, or up to the next tab stop), Shift+Tab
+    /// outdents the caret's lines, and Enter carries the line's indentation
+    /// over; returns `false` for keys left to the text box.
+    fn indent_key(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut Document,
+        key: &KeyEvent,
+        indentation: Indentation,
+    ) -> bool {
+        if key.mods.ctrl() || key.mods.meta() || key.mods.alt() {
+            return false;
+        }
+        let Some((carets, primary)) = self.carets(data) else {
+            return false;
+        };
+        let selection = carets[primary];
+        let text = &data.text;
+        let line_start = text[..selection.start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let prefix = &text[line_start..selection.start];
+        match &key.key {
+            Key::Tab if key.mods.shift() => {
+                let range = selection.start..selection.end;
+                if let Some(edit) = indent::outdent_lines(text, range, indentation) {
+                    self.replace_lines(ctx, data, edit);
+                }
+            }
+            Key::Tab if text[selection.start..selection.end].contains('\n') => {
+                let edit = indent::indent_lines(text, selection.start..selection.end, indentation);
+                self.replace_lines(ctx, data, edit);
+            }
+            Key::Tab => {
+                let fill = indentation.fill(prefix);
+                self.edit_carets(ctx, data, &Edit::Insert(&fill));
+            }
+            Key::Enter => {
+                let indent = &prefix[..prefix.len() - prefix.trim_start().len()];
+                if indent.is_empty() {
+                    return false;
+                }
+                let value = format!("\n{indent}");
+                self.edit_carets(ctx, data, &Edit::Insert(&value));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Makes a line edit as its own undo step and selects what it says.
+    fn replace_lines(&mut self, ctx: &mut EventCtx, data: &mut Document, edit: lines::Edit) {
+        data.checkpoint();
+        let mut text = data.text.clone();
+        text.replace_range(edit.range, &edit.replacement);
+        self.content.text = text.clone();
+        data.text = text;
+        ctx.invalidate_text_input(ImeInvalidation::Reset);
+        let selection = ByteRange {
+            start: edit.selection.start,
+            end: edit.selection.end,
+        };
+        self.set_carets(ctx, data, vec![selection], 0);
+    }
+
+    fn highlighting(&self) -> bool {
+        self.highlighter.language() != Language::Plain
+    }
+
+    pub fn textbox_id(&self) -> WidgetId {
+        self.textbox.id()
+    }
+
+    /// Where each visual line of the laid-out text starts, in this widget's
+    /// coordinate space. Wrapped text has several visual lines per line.
+    pub fn visual_lines(&self) -> Vec<VisualLine> {
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return Vec::new();
+        }
+        let session = component.borrow();
+        let Some(layout) = session.layout.layout() else {
+            return Vec::new();
+        };
+        let origin = self.text_origin();
+        (0..layout.line_count())
+            .filter_map(|index| layout.line_metric(index))
+            .map(|line| VisualLine {
+                start: line.start_offset,
+                y: origin.y + line.y_offset,
+                height: line.height,
+                baseline: origin.y + line.y_offset + line.baseline,
+            })
+            .collect()
+    }
+
+    fn text_origin(&self) -> Point {
+        self.textbox.layout_rect().origin() + self.textbox.widget().text_position().to_vec2()
+    }
+
+    /// The vertical extent of every visual line of the caret's line.
+    fn current_line_span(&self, text: &str) -> Option<(f64, f64)> {
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return None;
+        }
+        let session = component.borrow();
+        let layout = session.layout.layout()?;
+        let caret = session.selection().active.min(text.len());
+        let start = text[..caret].rfind('\n').map_or(0, |index| index + 1);
+        let end = text[caret..]
+            .find('\n')
+            .map_or(text.len(), |index| caret + index);
+        let first = layout.line_metric(layout.hit_test_text_position(start).line)?;
+        let last = layout.line_metric(layout.hit_test_text_position(end).line)?;
+        let origin = self.text_origin();
+        Some((
+            origin.y + first.y_offset,
+            origin.y + last.y_offset + last.height,
+        ))
+    }
+
+    /// Lays out the text box, narrowed to the wrap column when one is set.
+    fn layout_textbox(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let wrap_column = env.get(WRAP_COLUMN);
+        if wrap_column == 0 {
+            let size = self.textbox.layout(ctx, bc, &self.content, env);
+            self.textbox.set_origin(ctx, Point::ORIGIN);
+            return size;
+        }
+        // Narrow the text box so it wraps at the column; the body still fills
+        // the view so the background and rulers cover it.
+        let insets = env.get(theme::TEXTBOX_INSETS);
+        let border = env.get(theme::TEXTBOX_BORDER_WIDTH);
+        let width =
+            insets.x0 + insets.x1 + border * 2.0 + 1.0 + self.column_width * wrap_column as f64;
+        let max = Size::new(bc.max().width.min(width), bc.max().height);
+        let min = Size::new(bc.min().width.min(max.width), bc.min().height);
+        let size = self
+            .textbox
+            .layout(ctx, &BoxConstraints::new(min, max), &self.content, env);
+        self.textbox.set_origin(ctx, Point::ORIGIN);
+        bc.constrain(size)
+    }
+
+    fn caret(&self) -> Option<usize> {
+        let component = self.textbox.widget().text();
+        component
+            .can_read()
+            .then(|| component.borrow().selection().active)
+    }
+
+    /// Where Go To (or Select To) Matching Bracket leads from the caret. The
+    /// caret keeps its side of the bracket; a selection covers both.
+    pub fn matching_bracket(&self, text: &str, select: bool) -> Option<ByteRange> {
+        let caret = self.caret()?;
+        let (origin, partner) = brackets::pair_at(text, caret, &self.literals)?;
+        let width = |offset: usize| text[offset..].chars().next().map_or(0, char::len_utf8);
+        if select {
+            let end = origin.max(partner);
+            return Some(ByteRange {
+                start: origin.min(partner),
+                end: end + width(end),
+            });
+        }
+        let target = if caret == origin {
+            partner
+        } else {
+            partner + width(partner)
+        };
+        Some(ByteRange {
+            start: target,
+            end: target,
+        })
+    }
+
+    /// Outlines of the bracket pair around the caret.
+    fn bracket_rects(&self, text: &str) -> Vec<Rect> {
+        let Some((origin, partner)) = self
+            .caret()
+            .and_then(|caret| brackets::pair_at(text, caret, &self.literals))
+        else {
+            return Vec::new();
+        };
+        let component = self.textbox.widget().text();
+        let session = component.borrow();
+        let Some(layout) = session.layout.layout() else {
+            return Vec::new();
+        };
+        let offset = self.text_origin().to_vec2();
+        [origin, partner]
+            .into_iter()
+            .flat_map(|start| {
+                let end = start + text[start..].chars().next().map_or(0, char::len_utf8);
+                layout.rects_for_range(start..end)
+            })
+            .map(|rect| rect + offset)
+            .collect()
+    }
+
+    /// The caret's rectangle in this widget's coordinate space.
+    pub fn caret_rect(&self) -> Option<Rect> {
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return None;
+        }
+        let session = component.borrow();
+        let layout = session.layout.layout()?;
+        caret_rect_at(layout, session.selection().active)
+            .map(|rect| rect + self.text_origin().to_vec2())
+    }
+
+    /// The selected areas and carets of the extra carets, in this widget's
+    /// coordinate space.
+    fn extra_caret_rects(&self, text: &str, extra: &[ByteRange]) -> (Vec<Rect>, Vec<Rect>) {
+        let component = self.textbox.widget().text();
+        if extra.is_empty() || !component.can_read() {
+            return (Vec::new(), Vec::new());
+        }
+        let session = component.borrow();
+        let Some(layout) = session.layout.layout() else {
+            return (Vec::new(), Vec::new());
+        };
+        let offset = self.text_origin().to_vec2();
+        let valid = extra
+            .iter()
+            .filter(|range| range.start <= range.end && range.end <= text.len());
+        let selections = valid
+            .clone()
+            .filter(|range| range.start < range.end)
+            .flat_map(|range| layout.rects_for_range(range.start..range.end))
+            .map(|rect| rect + offset)
+            .collect();
+        let carets = valid
+            .filter_map(|range| caret_rect_at(layout, range.end))
+            .map(|rect| rect + offset)
+            .collect();
+        (selections, carets)
+    }
+
+    /// Copies the text box's selection into the document, with the metrics
+    /// the status bar shows.
+    pub fn update_selection(&mut self, data: &mut Document) {
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return;
+        }
+        let handle = component.borrow();
+        let selection = handle.selection();
+        let byte_start = selection.min();
+        let byte_end = selection.max();
+        let chars_before_start = count_chars(&data.text, byte_start);
+        let chars_before_end = count_chars(&data.text, byte_end);
+        let char_range = CharRange {
+            start: chars_before_start,
+            end: chars_before_end,
+        };
+        data.selection = SelectionState {
+            char_range,
+            byte_range: ByteRange {
+                start: byte_start,
+                end: byte_end,
+            },
+            extra: data.selection.extra.clone(),
+        };
+        let column = column_from_bytes(&data.text, byte_start);
+        let line = line_from_bytes(&data.text, byte_start);
+        data.metrics.line = line;
+        data.metrics.column = column;
+        data.metrics.chars = data.text.chars().count();
+        data.metrics.selection = char_range.len();
+    }
+}
+
+impl Widget<Document> for EditorBody {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Document, env: &Env) {
+        // Folds carried across an edit in `update` reach the document here.
+        if !self.folds.same(&data.folds) {
+            data.folds = self.folds.clone();
+        }
+        // The text box asks to scroll itself into view when focused, which for
+        // a full-height box means jumping to the top. The editor follows the
+        // caret instead.
+        if let Event::Notification(notification) = event
+            && notification.is(SCROLL_TO_VIEW)
+        {
+            ctx.set_handled();
+            return;
+        }
+
+        if let Event::Command(cmd) = event {
+            // Search and Go To land here too; whatever they select is revealed.
+            if let Some(range) = cmd.get(APPLY_SELECTION) {
+                self.unfold(ctx, data, *range);
+                ctx.set_handled();
+                return;
+            }
+            if let Some(&line) = cmd.get(TOGGLE_FOLD) {
+                self.toggle_fold(ctx, data, line);
+                ctx.set_handled();
+                return;
+            }
+            if cmd.is(FOLD_ALL) {
+                let folds = self
+                    .fold_regions()
+                    .iter()
+                    .map(|region| region.hidden.clone());
+                let folds = Folds::new(folds.collect(), &data.text);
+                self.set_folds(ctx, data, folds);
+                self.move_caret_out_of_folds(ctx, data);
+                ctx.set_handled();
+                return;
+            }
+            if cmd.is(UNFOLD_ALL) {
+                self.set_folds(ctx, data, Folds::default());
+                ctx.set_handled();
+                return;
+            }
+            if cmd.is(ADD_CARET_ABOVE) || cmd.is(ADD_CARET_BELOW) {
+                self.add_caret(ctx, data, cmd.is(ADD_CARET_ABOVE));
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        match event {
+            Event::MouseDown(mouse)
+                if mouse.mods.alt() && !mouse.mods.ctrl() && !mouse.mods.meta() =>
+            {
+                if let Some((line, column)) = self.block_position(&data.text, mouse.pos) {
+                    ctx.set_active(true);
+                    ctx.set_focus(self.textbox_id());
+                    self.block_dragging = true;
+                    self.set_block(ctx, data, Block::at(line, column));
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseMove(mouse) if self.block_dragging => {
+                if let (Some(block), Some(active)) =
+                    (self.block, self.block_position(&data.text, mouse.pos))
+                {
+                    self.set_block(ctx, data, Block { active, ..block });
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseUp(_) if self.block_dragging => {
+                self.block_dragging = false;
+                ctx.set_active(false);
+                ctx.set_handled();
+                return;
+            }
+            Event::KeyDown(key)
+                if self.block.is_some() && self.block_key(ctx, data, key, indentation(env)) =>
+            {
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(commands::COPY) || cmd.is(commands::CUT) => {
+                if let Some(block) = self.current_block(data) {
+                    self.copy_block(ctx, data, block, cmd.is(commands::CUT));
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            Event::Paste(clipboard) => {
+                if let Some(value) = clipboard.get_string()
+                    && self.paste_block(ctx, data, &value)
+                {
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        match event {
+            // Cmd+click keeps the current caret and adds the clicked one.
+            Event::MouseDown(mouse) if mouse.mods.raw().contains(keymap::command_modifier()) => {
+                if let Some((carets, _)) = self.carets(data) {
+                    data.selection.extra = Arc::new(carets);
+                }
+            }
+            Event::MouseDown(_) => self.clear_extra_carets(ctx, data),
+            Event::KeyDown(key)
+                if !data.selection.extra.is_empty()
+                    && self.multi_caret_key(ctx, data, key, indentation(env)) =>
+            {
+                ctx.set_handled();
+                return;
+            }
+            Event::KeyDown(key) if self.indent_key(ctx, data, key, indentation(env)) => {
+                ctx.set_handled();
+                return;
+            }
+            Event::Paste(clipboard) if !data.selection.extra.is_empty() => {
+                if let Some(value) = clipboard.get_string() {
+                    self.edit_carets(ctx, data, &Edit::Insert(&value));
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        self.textbox.event(ctx, event, &mut self.content, env);
+        // A Cmd+click on an existing caret merges with it rather than doubling it.
+        if let Event::MouseDown(_) = event
+            && !data.selection.extra.is_empty()
+            && let Some((carets, primary)) = self.carets(data)
+        {
+            self.set_carets(ctx, data, carets, primary);
+        }
+        // Only events that can edit are worth comparing the text for.
+        let editing = matches!(
+            event,
+            Event::KeyDown(_) | Event::Paste(_) | Event::ImeStateChange | Event::Command(_)
+        );
+        if editing && self.content.text != data.text {
+            data.history
+                .record_typing(&data.text, data.selection.byte_range);
+            data.text = self.content.text.clone();
+        }
+        // Moving the caret into a fold opens it.
+        if let Some(caret) = self.caret() {
+            self.unfold(
+                ctx,
+                data,
+                ByteRange {
+                    start: caret,
+                    end: caret,
+                },
+            );
+        }
+        self.update_selection(data);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &Document, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.content.text = data.text.clone();
+            self.folds = data.folds.follow(&data.text);
+            self.content.folds = self.folds.ranges.clone();
+        }
+        self.textbox.lifecycle(ctx, event, &self.content, env);
+        if let LifeCycle::WidgetAdded = event {
+            // The editor is rebuilt on tab switches; pick up where this document left off.
+            let _ = self.set_selection(data.selection.byte_range);
+            let language = Language::from_path(data.pathbuf().as_deref());
+            self.highlighter = Highlighter::new(language, &data.text);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Document, data: &Document, env: &Env) {
+        if !old_data.text.same(&data.text) {
+            // Edits made in the text box are already in `content`.
+            if self.content.text != data.text {
+                self.content.text = data.text.clone();
+            }
+            if !self.folds.ranges.is_empty() {
+                self.folds = self.folds.follow(&data.text);
+                self.content.folds = self.folds.ranges.clone();
+            }
+            self.highlighter.update(&old_data.text, &data.text);
+            self.styled = None;
+            self.regions = None;
+        }
+        self.textbox.update(ctx, &self.content, env);
+        if !old_data.file_path.same(&data.file_path) {
+            let language = Language::from_path(data.pathbuf().as_deref());
+            if language != self.highlighter.language() {
+                self.highlighter = Highlighter::new(language, &data.text);
+                self.styled = None;
+                self.literals.clear();
+                self.regions = None;
+                ctx.request_layout();
+            }
+        }
+        if ctx.env_changed() {
+            self.styled = None;
+            self.regions = None;
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &Document,
+        env: &Env,
+    ) -> Size {
+        self.column_width = overlay::column_width(ctx.text(), env);
+        // Highlighted text is drawn from our own layout, so the text box's
+        // copy is made invisible rather than painted twice.
+        let text_color = if self.highlighting() {
+            KeyOrValue::Concrete(Color::TRANSPARENT)
+        } else {
+            KeyOrValue::Key(theme::TEXT_COLOR)
+        };
+        self.textbox.widget_mut().set_text_color(text_color);
+        let size = self.layout_textbox(ctx, bc, env);
+        if self.highlighting() {
+            let insets = env.get(theme::TEXTBOX_INSETS);
+            let wrap_width = if self.wrap {
+                self.textbox.layout_rect().width() - insets.x_value()
+            } else {
+                f64::INFINITY
+            };
+            if self.styled.is_none() || wrap_width != self.styled_width {
+                self.styled = highlight::styled_layout(
+                    ctx.text(),
+                    &self.content,
+                    &self.highlighter,
+                    wrap_width,
+                    env,
+                );
+                self.styled_width = wrap_width;
+                self.literals = self.highlighter.literals();
+            }
+        }
+        if self.regions.is_none() {
+            let tab_width = (env.get(TAB_WIDTH) as usize).max(1);
+            self.regions = Some(folding::regions(&data.text, &self.literals, tab_width));
+        }
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Document, env: &Env) {
+        // The text box fills its own background, so the current line band and
+        // rulers are drawn first and the box painted over them with a clear one.
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &env.get(theme::BACKGROUND_LIGHT));
+        if env.get(HIGHLIGHT_CURRENT_LINE)
+            && let Some((top, bottom)) = self.current_line_span(&data.text)
+        {
+            overlay::paint_current_line(ctx, top, bottom, env);
+        }
+        for rect in self.bracket_rects(&data.text) {
+            overlay::paint_bracket(ctx, rect, env);
+        }
+        let (selections, carets) = self.extra_caret_rects(&data.text, &data.selection.extra);
+        for rect in selections {
+            overlay::paint_selection(ctx, rect, env);
+        }
+        let left = self.text_origin().x;
+        overlay::paint_rulers(ctx, left, self.column_width, &env.get(RULERS), env);
+        let clear = env
+            .clone()
+            .adding(theme::BACKGROUND_LIGHT, Color::TRANSPARENT);
+        self.textbox.paint(ctx, &self.content, &clear);
+        if self.highlighting()
+            && let Some(styled) = &self.styled
+        {
+            ctx.draw_text(styled, self.text_origin());
+        }
+        for rect in carets {
+            overlay::paint_caret(ctx, rect, env);
+        }
+
+        let component = self.textbox.widget().text();
+        if !component.can_read() {
+            return;
+        }
+        let session = component.borrow();
+        let Some(layout) = session.layout.layout() else {
+            return;
+        };
+        let origin = self.text_origin();
+        let folds = &self.content.folds;
+        for fold in folds.iter() {
+            // Nested folds are inside an outer one's placeholder.
+            if !folds.iter().any(|outer| is_hidden(outer, fold.start)) {
+                overlay::paint_fold_placeholder(ctx, layout, origin, fold.start, env);
+            }
+        }
+        if env.get(SHOW_WHITESPACE) {
+            overlay::paint_whitespace(ctx, layout, origin, &data.text, folds, env);
+        }
+    }
+}
+
+fn caret_rect_at(layout: &PietTextLayout, offset: usize) -> Option<Rect> {
+    let hit = layout.hit_test_text_position(offset);
+    let line = layout.line_metric(hit.line)?;
+    Some(Rect::new(
+        hit.point.x,
+        line.y_offset,
+        hit.point.x + 1.0,
+        line.y_offset + line.height,
+    ))
+}
+
+fn indentation(env: &Env) -> Indentation {
+    Indentation {
+        tabs: env.get(INDENT_WITH_TABS),
+        width: (env.get(INDENT_WIDTH) as usize).max(1),
+    }
+}
+
+/// Whether `fold` hides the position `offset`. The fold's start, the end of
+/// its first line, stays visible.
+fn is_hidden(fold: &Range<usize>, offset: usize) -> bool {
+    fold.start < offset && offset <= fold.end
+}
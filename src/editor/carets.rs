@@ -0,0 +1,140 @@
+use super::ByteRange;
+
+pub enum Edit<'a> {
+    Insert(&'a str),
+    Backspace,
+    Delete,
+}
+
+pub enum Motion {
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+/// Sorts the carets, merging any that overlap, and returns them with the
+/// index of the primary one. Ranges run from `start` to the caret at `end`.
+pub fn normalize(primary: ByteRange, extra: &[ByteRange]) -> (Vec<ByteRange>, usize) {
+    let mut tagged: Vec<(ByteRange, bool)> = extra.iter().map(|&range| (range, false)).collect();
+    tagged.push((primary, true));
+    tagged.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut carets: Vec<ByteRange> = Vec::with_capacity(tagged.len());
+    let mut primary_index = 0;
+    for (range, is_primary) in tagged {
+        if let Some(last) = carets.last_mut()
+            && overlaps(last, &range)
+        {
+            last.end = last.end.max(range.end);
+        } else {
+            carets.push(range);
+        }
+        if is_primary {
+            primary_index = carets.len() - 1;
+        }
+    }
+    (carets, primary_index)
+}
+
+/// Ranges that share text, or a caret touching another caret or range.
+fn overlaps(first: &ByteRange, second: &ByteRange) -> bool {
+    second.start < first.end
+        || (second.start == first.end && (first.start == first.end || second.start == second.end))
+}
+
+/// Applies `edit` at every caret (sorted, as from `normalize`); returns the
+/// new text and where each caret ends up.
+pub fn apply(text: &str, carets: &[ByteRange], edit: &Edit) -> (String, Vec<ByteRange>) {
+    let mut result = String::with_capacity(text.len());
+    let mut moved = Vec::with_capacity(carets.len());
+    let mut copied = 0;
+    for caret in carets {
+        let (start, end) = match edit {
+            Edit::Backspace if caret.start == caret.end => {
+                (prev_char(text, caret.start), caret.end)
+            }
+            Edit::Delete if caret.start == caret.end => (caret.start, next_char(text, caret.end)),
+            _ => (caret.start, caret.end),
+        };
+        // Two carets deleting towards each other share the character between.
+        let start = start.max(copied);
+        result.push_str(&text[copied..start]);
+        if let Edit::Insert(value) = edit {
+            result.push_str(value);
+        }
+        moved.push(ByteRange {
+            start: result.len(),
+            end: result.len(),
+        });
+        copied = end.max(start);
+    }
+    result.push_str(&text[copied..]);
+    (result, moved)
+}
+
+/// Moves every caret one step; a selection collapses to the side moved to.
+pub fn move_all(text: &str, carets: &[ByteRange], motion: &Motion) -> Vec<ByteRange> {
+    carets
+        .iter()
+        .map(|caret| {
+            let offset = match motion {
+                Motion::Left if caret.start != caret.end => caret.start,
+                Motion::Right if caret.start != caret.end => caret.end,
+                Motion::Left => prev_char(text, caret.end),
+                Motion::Right => next_char(text, caret.end),
+                Motion::Home => line_start(text, caret.end),
+                Motion::End => line_end(text, caret.end),
+            };
+            ByteRange {
+                start: offset,
+                end: offset,
+            }
+        })
+        .collect()
+}
+
+/// The offset on the line above or below `caret` in the same column, or at
+/// the end of that line when it is shorter.
+pub fn adjacent_line(text: &str, caret: usize, above: bool) -> Option<usize> {
+    let start = line_start(text, caret);
+    let column = text[start..caret].chars().count();
+    let target = if above {
+        line_start(text, start.checked_sub(1)?)
+    } else {
+        line_end(text, caret)
+            .checked_add(1)
+            .filter(|&next| next <= text.len())?
+    };
+    let line = &text[target..line_end(text, target)];
+    Some(
+        target
+            + line
+                .char_indices()
+                .nth(column)
+                .map_or(line.len(), |(index, _)| index),
+    )
+}
+
+fn prev_char(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .chars()
+        .next_back()
+        .map_or(offset, |ch| offset - ch.len_utf8())
+}
+
+fn next_char(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .chars()
+        .next()
+        .map_or(offset, |ch| offset + ch.len_utf8())
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn line_end(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .find('\n')
+        .map_or(text.len(), |index| offset + index)
+}
@@ -0,0 +1,173 @@
+use druid::kurbo::{Point, Rect, Size};
+use druid::piet::{Text, TextAttribute, TextLayout, TextLayoutBuilder};
+use druid::widget::prelude::*;
+use druid::{Color, Key};
+
+use crate::theme::{GUTTER_ACTIVE_TEXT, GUTTER_BACKGROUND, GUTTER_TEXT};
+
+use super::body::{EditorBody, VisualLine};
+use super::{ByteRange, EDITOR_FONT_KEY};
+
+const PADDING: f64 = 8.0;
+const MIN_DIGITS: usize = 2;
+const MARKER_WIDTH: f64 = 14.0;
+const FOLDED_MARKER: &str = "\u{25b8}";
+const UNFOLDED_MARKER: &str = "\u{25be}";
+
+/// The line number and fold marker columns to the left of the text.
+#[derive(Default)]
+pub struct Gutter {
+    pub width: f64,
+    show_numbers: bool,
+    /// The line a gutter drag started on; the selection always covers it.
+    drag_anchor: Option<ByteRange>,
+}
+
+impl Gutter {
+    /// Sizes the gutter to fit the largest line number of `text`, if numbers
+    /// are shown, and the fold markers.
+    pub fn measure(&mut self, ctx: &mut LayoutCtx, text: &str, show_numbers: bool, env: &Env) {
+        self.show_numbers = show_numbers;
+        self.width = MARKER_WIDTH;
+        if show_numbers {
+            let lines = text.matches('\n').count() + 1;
+            let digits = lines.to_string().len().max(MIN_DIGITS);
+            let layout = number_layout(ctx.text(), &"0".repeat(digits), GUTTER_TEXT, env);
+            self.width += layout.size().width + PADDING * 2.0;
+        }
+    }
+
+    /// The start of the line whose fold marker column is at (`x`, `y`), with
+    /// `y` in text coordinates.
+    pub fn marker_at(&self, lines: &[VisualLine], text: &str, x: f64, y: f64) -> Option<usize> {
+        (x >= self.width - MARKER_WIDTH && x < self.width).then(|| line_at(lines, text, y).start)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag_anchor.is_some()
+    }
+
+    /// Starts a line selection at `y` (in text coordinates); returns the
+    /// range to select as (anchor, active).
+    pub fn press(&mut self, lines: &[VisualLine], text: &str, y: f64) -> ByteRange {
+        let line = line_at(lines, text, y);
+        self.drag_anchor = Some(line);
+        line
+    }
+
+    /// Extends the selection started by `press` to the line at `y`.
+    pub fn drag(&self, lines: &[VisualLine], text: &str, y: f64) -> Option<ByteRange> {
+        let anchor = self.drag_anchor?;
+        let line = line_at(lines, text, y);
+        Some(if line.start >= anchor.start {
+            ByteRange {
+                start: anchor.start,
+                end: line.end,
+            }
+        } else {
+            ByteRange {
+                start: anchor.end,
+                end: line.start,
+            }
+        })
+    }
+
+    pub fn release(&mut self) {
+        self.drag_anchor = None;
+    }
+
+    /// Paints numbers and fold markers next to the first visual line of every
+    /// line, skipping wrapped continuations and folded lines. `scroll_y`
+    /// converts text to widget coordinates.
+    pub fn paint(
+        &self,
+        ctx: &mut PaintCtx,
+        body: &EditorBody,
+        text: &str,
+        caret_line: usize,
+        scroll_y: f64,
+        env: &Env,
+    ) {
+        let height = ctx.size().height;
+        let rect = Rect::from_origin_size(Point::ORIGIN, Size::new(self.width, height));
+        ctx.fill(rect, &env.get(GUTTER_BACKGROUND));
+        let regions = body.fold_regions();
+        let folds = body.folds();
+        ctx.with_save(|ctx| {
+            ctx.clip(rect);
+            let mut number = 0;
+            for line in body.visual_lines() {
+                if !starts_line(text, line.start) {
+                    continue;
+                }
+                number += 1;
+                let top = line.y - scroll_y;
+                if top + line.height < 0.0
+                    || top > height
+                    || folds
+                        .iter()
+                        .any(|fold| fold.start < line.start && line.start <= fold.end)
+                {
+                    continue;
+                }
+                let baseline = line.baseline - scroll_y;
+                if self.show_numbers {
+                    let color = if number == caret_line {
+                        GUTTER_ACTIVE_TEXT
+                    } else {
+                        GUTTER_TEXT
+                    };
+                    let layout = number_layout(ctx.text(), &number.to_string(), color, env);
+                    let ascent = layout.line_metric(0).map_or(0.0, |metric| metric.baseline);
+                    let x = self.width - MARKER_WIDTH - PADDING - layout.size().width;
+                    ctx.draw_text(&layout, (x, baseline - ascent));
+                }
+                if let Ok(index) = regions.binary_search_by_key(&line.start, |region| region.line) {
+                    let marker = if folds.contains(&regions[index].hidden) {
+                        FOLDED_MARKER
+                    } else {
+                        UNFOLDED_MARKER
+                    };
+                    let layout = number_layout(ctx.text(), marker, GUTTER_TEXT, env);
+                    let ascent = layout.line_metric(0).map_or(0.0, |metric| metric.baseline);
+                    let x = self.width - MARKER_WIDTH + (MARKER_WIDTH - layout.size().width) / 2.0;
+                    ctx.draw_text(&layout, (x, baseline - ascent));
+                }
+            }
+        });
+    }
+}
+
+fn number_layout<T: Text>(
+    text: &mut T,
+    number: &str,
+    color: Key<Color>,
+    env: &Env,
+) -> T::TextLayout {
+    let font = env.get(EDITOR_FONT_KEY);
+    text.new_text_layout(number.to_string())
+        .font(font.family.clone(), font.size)
+        .default_attribute(TextAttribute::Weight(font.weight))
+        .text_color(env.get(color))
+        .build()
+        .expect("laying out gutter text cannot fail")
+}
+
+fn starts_line(text: &str, offset: usize) -> bool {
+    offset == 0 || text.as_bytes().get(offset - 1) == Some(&b'\n')
+}
+
+/// The whole line (including its newline) under `y`, as a byte range.
+fn line_at(lines: &[VisualLine], text: &str, y: f64) -> ByteRange {
+    let visual = lines
+        .iter()
+        .rev()
+        .find(|line| line.y <= y)
+        .or(lines.first())
+        .map_or(0, |line| line.start.min(text.len()));
+    let start = text[..visual].rfind('\n').map_or(0, |index| index + 1);
+    let end = text[visual..]
+        .find('\n')
+        .map_or(text.len(), |index| visual + index + 1);
+    ByteRange { start, end }
+}
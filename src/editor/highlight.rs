@@ -0,0 +1,48 @@
+use druid::piet::{PietText, PietTextLayout, Text, TextAttribute, TextLayoutBuilder};
+use druid::{Color, Env, Key, theme};
+
+use crate::syntax::{Highlighter, TokenKind};
+use crate::theme::{
+    SYNTAX_ATTRIBUTE, SYNTAX_COMMENT, SYNTAX_EMPHASIS, SYNTAX_HEADING, SYNTAX_KEYWORD,
+    SYNTAX_NUMBER, SYNTAX_STRING, SYNTAX_TYPE,
+};
+
+use super::EDITOR_FONT_KEY;
+use super::text::{EditorText, hide_folds};
+
+fn token_color(kind: TokenKind) -> Key<Color> {
+    match kind {
+        TokenKind::Keyword => SYNTAX_KEYWORD,
+        TokenKind::Type => SYNTAX_TYPE,
+        TokenKind::String => SYNTAX_STRING,
+        TokenKind::Number => SYNTAX_NUMBER,
+        TokenKind::Comment => SYNTAX_COMMENT,
+        TokenKind::Attribute => SYNTAX_ATTRIBUTE,
+        TokenKind::Heading => SYNTAX_HEADING,
+        TokenKind::Emphasis => SYNTAX_EMPHASIS,
+    }
+}
+
+/// Lays the text out exactly as the text box does, but with token colors.
+/// Only color changes, so glyphs land where the text box put them.
+pub fn styled_layout(
+    factory: &mut PietText,
+    text: &EditorText,
+    highlighter: &Highlighter,
+    wrap_width: f64,
+    env: &Env,
+) -> Option<PietTextLayout> {
+    let font = env.get(EDITOR_FONT_KEY);
+    let mut builder = factory
+        .new_text_layout(text.text.clone())
+        .max_width(wrap_width)
+        .font(font.family.clone(), font.size)
+        .default_attribute(font.weight)
+        .default_attribute(font.style)
+        .default_attribute(TextAttribute::TextColor(env.get(theme::TEXT_COLOR)));
+    for span in highlighter.spans() {
+        let color = env.get(token_color(span.kind));
+        builder = builder.range_attribute(span.start..span.end, TextAttribute::TextColor(color));
+    }
+    hide_folds(builder, &text.folds).build().ok()
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use druid::Data;
+use druid::im::Vector;
+
+use super::ByteRange;
+
+/// Edits closer together than this are merged into a single undo step.
+const COALESCE_WINDOW: Duration = Duration::from_millis(1000);
+const MAX_UNDO_STEPS: usize = 500;
+
+#[derive(Clone, Data, Debug)]
+pub struct Revision {
+    pub text: Arc<String>,
+    pub selection: ByteRange,
+}
+
+#[derive(Clone, Data, Debug, Default)]
+pub struct History {
+    undo: Vector<Revision>,
+    redo: Vector<Revision>,
+    #[data(ignore)]
+    last_edit: Option<Instant>,
+}
+
+impl History {
+    /// Records the state *before* an edit made while typing. Bursts of typing
+    /// collapse into one step.
+    pub fn record_typing(&mut self, before: &str, selection: ByteRange) {
+        let now = Instant::now();
+        let recent = self
+            .last_edit
+            .is_some_and(|last| now.duration_since(last) < COALESCE_WINDOW);
+        self.last_edit = Some(now);
+        if !recent {
+            self.push(before, selection);
+        }
+        self.redo.clear();
+    }
+
+    /// Records the state before a command-driven edit as its own undo step.
+    pub fn checkpoint(&mut self, before: &str, selection: ByteRange) {
+        self.last_edit = None;
+        self.push(before, selection);
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, current: &str, selection: ByteRange) -> Option<Revision> {
+        let revision = self.undo.pop_back()?;
+        self.redo.push_back(Revision {
+            text: Arc::new(current.to_string()),
+            selection,
+        });
+        self.last_edit = None;
+        Some(revision)
+    }
+
+    pub fn redo(&mut self, current: &str, selection: ByteRange) -> Option<Revision> {
+        let revision = self.redo.pop_back()?;
+        self.undo.push_back(Revision {
+            text: Arc::new(current.to_string()),
+            selection,
+        });
+        self.last_edit = None;
+        Some(revision)
+    }
+
+    fn push(&mut self, before: &str, selection: ByteRange) {
+        if self
+            .undo
+            .back()
+            .is_some_and(|last| last.text.as_str() == before)
+        {
+            return;
+        }
+        self.undo.push_back(Revision {
+            text: Arc::new(before.to_string()),
+            selection,
+        });
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.pop_front();
+        }
+    }
+}
@@ -1,11 +1,37 @@
-use druid::kurbo::Point;
-use druid::text::{FontDescriptor, Selection};
-use druid::widget::TextBox;
+use std::sync::Arc;
+
+use druid::kurbo::{Point, Vec2};
+use druid::text::FontDescriptor;
+use druid::widget::Scroll;
 use druid::widget::prelude::*;
-use druid::{Key, KeyOrValue, Lens, Selector, WidgetPod};
+use druid::{Key, Lens, Selector, WidgetPod, commands};
+
+use crate::app::document::Document;
+
+mod block;
+mod body;
+mod carets;
+mod gutter;
+mod highlight;
+mod history;
+mod overlay;
+mod text;
+
+use body::EditorBody;
+use gutter::Gutter;
+pub use history::History;
 
 pub const APPLY_SELECTION: Selector<ByteRange> = Selector::new("rustpad.editor.apply-selection");
 pub const REQUEST_EDITOR_FOCUS: Selector<()> = Selector::new("rustpad.editor.focus");
+pub const GO_TO_MATCHING_BRACKET: Selector<()> = Selector::new("rustpad.editor.go-to-bracket");
+pub const SELECT_TO_MATCHING_BRACKET: Selector<()> =
+    Selector::new("rustpad.editor.select-to-bracket");
+pub const FOLD_ALL: Selector<()> = Selector::new("rustpad.editor.fold-all");
+pub const UNFOLD_ALL: Selector<()> = Selector::new("rustpad.editor.unfold-all");
+pub const ADD_CARET_ABOVE: Selector<()> = Selector::new("rustpad.editor.add-caret-above");
+pub const ADD_CARET_BELOW: Selector<()> = Selector::new("rustpad.editor.add-caret-below");
+/// Sent by the gutter with the start of the line whose fold marker was clicked.
+const TOGGLE_FOLD: Selector<usize> = Selector::new("rustpad.editor.toggle-fold");
 
 #[derive(Clone, Copy, Data, Debug, Default, PartialEq)]
 pub struct ByteRange {
@@ -27,6 +53,26 @@ pub struct EditorMetrics {
 pub struct SelectionState {
     pub char_range: CharRange,
     pub byte_range: ByteRange,
+    /// Carets added beside the text box's own, each running from `start` to
+    /// the caret at `end`.
+    pub extra: Arc<Vec<ByteRange>>,
+}
+
+impl SelectionState {
+    pub fn from_bytes(text: &str, range: ByteRange) -> Self {
+        let range = ByteRange {
+            start: floor_char_boundary(text, range.start),
+            end: floor_char_boundary(text, range.end),
+        };
+        Self {
+            char_range: CharRange {
+                start: count_chars(text, range.start),
+                end: count_chars(text, range.end),
+            },
+            byte_range: range,
+            extra: Arc::default(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Data, Debug, Default, PartialEq, Eq)]
@@ -42,106 +88,172 @@ impl CharRange {
 }
 
 pub const EDITOR_FONT_KEY: Key<FontDescriptor> = Key::new("rustpad.editor.font");
+pub const SHOW_LINE_NUMBERS: Key<bool> = Key::new("rustpad.editor.show-line-numbers");
+pub const HIGHLIGHT_CURRENT_LINE: Key<bool> = Key::new("rustpad.editor.highlight-current-line");
+pub const SHOW_WHITESPACE: Key<bool> = Key::new("rustpad.editor.show-whitespace");
+pub const RULERS: Key<Arc<Vec<u16>>> = Key::new("rustpad.editor.rulers");
+/// Column to wrap lines at; 0 wraps at the window edge.
+pub const WRAP_COLUMN: Key<u64> = Key::new("rustpad.editor.wrap-column");
+pub const INDENT_WITH_TABS: Key<bool> = Key::new("rustpad.editor.indent-with-tabs");
+/// Columns per tab, or spaces per level when indenting with spaces.
+pub const INDENT_WIDTH: Key<u64> = Key::new("rustpad.editor.indent-width");
+pub const TAB_WIDTH: Key<u64> = Key::new("rustpad.editor.tab-width");
 
+/// The editing surface: a text box laid out at full height inside a scroll
+/// view we own, so the scroll offset can be saved and restored per document.
 pub struct EditorWidget {
-    textbox: WidgetPod<String, TextBox<String>>,
+    scroll: WidgetPod<Document, Scroll<Document, EditorBody>>,
+    gutter: Gutter,
+    follow_caret: bool,
+    restore_scroll: bool,
 }
 
 impl EditorWidget {
     pub fn new(wrap: bool) -> Self {
-        let textbox = TextBox::multiline()
-            .with_font(KeyOrValue::Key(EDITOR_FONT_KEY))
-            .with_line_wrapping(wrap);
+        let scroll = Scroll::new(EditorBody::new(wrap)).content_must_fill(true);
+        let scroll = if wrap { scroll.vertical() } else { scroll };
         Self {
-            textbox: WidgetPod::new(textbox),
+            scroll: WidgetPod::new(scroll),
+            gutter: Gutter::default(),
+            follow_caret: false,
+            restore_scroll: true,
         }
     }
 
-    fn update_selection(&mut self, data: &mut crate::app::AppState) {
-        let component = self.textbox.widget().text();
-        if !component.can_read() {
-            return;
-        }
-        let handle = component.borrow();
-        let selection = handle.selection();
-        let byte_start = selection.min();
-        let byte_end = selection.max();
-        let chars_before_start = count_chars(&data.text, byte_start);
-        let chars_before_end = count_chars(&data.text, byte_end);
-        let char_range = CharRange {
-            start: chars_before_start,
-            end: chars_before_end,
-        };
-        data.selection = SelectionState {
-            char_range,
-            byte_range: ByteRange {
-                start: byte_start,
-                end: byte_end,
-            },
+    fn body(&self) -> &EditorBody {
+        self.scroll.widget().child()
+    }
+
+    fn body_mut(&mut self) -> &mut EditorBody {
+        self.scroll.widget_mut().child_mut()
+    }
+
+    /// Gutter clicks and drags select whole lines; clicks on a fold marker
+    /// fold or unfold its block.
+    fn gutter_event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Document) -> bool {
+        let scroll_y = self.scroll.widget().offset().y;
+        let range = match event {
+            Event::MouseDown(mouse)
+                if let Some(line) = self.gutter.marker_at(
+                    &self.body().visual_lines(),
+                    &data.text,
+                    mouse.pos.x,
+                    mouse.pos.y + scroll_y,
+                ) && self
+                    .body()
+                    .fold_regions()
+                    .iter()
+                    .any(|region| region.line == line) =>
+            {
+                ctx.submit_command(TOGGLE_FOLD.with(line).to(ctx.widget_id()));
+                None
+            }
+            Event::MouseDown(mouse) if mouse.pos.x < self.gutter.width => {
+                ctx.set_active(true);
+                ctx.set_focus(self.body().textbox_id());
+                let lines = self.body().visual_lines();
+                Some(
+                    self.gutter
+                        .press(&lines, &data.text, mouse.pos.y + scroll_y),
+                )
+            }
+            Event::MouseMove(mouse) if self.gutter.is_dragging() => {
+                let lines = self.body().visual_lines();
+                self.gutter.drag(&lines, &data.text, mouse.pos.y + scroll_y)
+            }
+            Event::MouseUp(_) if self.gutter.is_dragging() => {
+                ctx.set_active(false);
+                self.gutter.release();
+                None
+            }
+            _ => return false,
         };
-        let column = column_from_bytes(&data.text, byte_start);
-        let line = line_from_bytes(&data.text, byte_start);
-        data.metrics.line = line;
-        data.metrics.column = column;
-        data.metrics.chars = data.text.chars().count();
-        data.metrics.selection = char_range.len();
+        if let Some(range) = range {
+            if let Some(inval) = self.body_mut().set_selection(range) {
+                ctx.invalidate_text_input(inval);
+            }
+            self.body_mut().update_selection(data);
+            self.follow_caret = true;
+            ctx.request_layout();
+        }
+        ctx.set_handled();
+        true
     }
 }
 
-impl Widget<crate::app::AppState> for EditorWidget {
-    fn event(
-        &mut self,
-        ctx: &mut EventCtx,
-        event: &Event,
-        data: &mut crate::app::AppState,
-        env: &Env,
-    ) {
+impl Widget<Document> for EditorWidget {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Document, env: &Env) {
         match event {
             Event::Command(cmd) if cmd.is(APPLY_SELECTION) => {
                 if let Some(range) = cmd.get(APPLY_SELECTION) {
-                    let selection = Selection::new(range.start, range.end);
-                    if let Some(inval) = self
-                        .textbox
-                        .widget_mut()
-                        .text_mut()
-                        .borrow_mut()
-                        .set_selection(selection)
-                    {
+                    if let Some(inval) = self.body_mut().set_selection(*range) {
                         ctx.invalidate_text_input(inval);
                     }
-                    ctx.set_handled();
+                    // The body still sees the command, to open any fold it lands in.
+                    self.follow_caret = true;
+                    ctx.request_layout();
                 }
             }
             Event::Command(cmd) if cmd.is(REQUEST_EDITOR_FOCUS) => {
-                ctx.request_focus();
+                ctx.set_focus(self.body().textbox_id());
+                ctx.set_handled();
+            }
+            Event::Command(cmd)
+                if cmd.is(GO_TO_MATCHING_BRACKET) || cmd.is(SELECT_TO_MATCHING_BRACKET) =>
+            {
+                let select = cmd.is(SELECT_TO_MATCHING_BRACKET);
+                if let Some(range) = self.body().matching_bracket(&data.text, select) {
+                    if let Some(inval) = self.body_mut().set_selection(range) {
+                        ctx.invalidate_text_input(inval);
+                    }
+                    self.body_mut().update_selection(data);
+                    self.follow_caret = true;
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(commands::UNDO) || cmd.is(commands::REDO) => {
+                self.body_mut()
+                    .apply_revision(ctx, data, cmd.is(commands::UNDO));
+                self.follow_caret = true;
                 ctx.set_handled();
+                return;
+            }
+            _ => {
+                if self.gutter_event(ctx, event, data) {
+                    return;
+                }
             }
-            _ => {}
         }
 
-        self.textbox.event(ctx, event, &mut data.text, env);
-        self.update_selection(data);
+        let selection = data.selection.byte_range;
+        self.scroll.event(ctx, event, data, env);
+        if data.selection.byte_range != selection {
+            self.follow_caret = true;
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+        data.scroll_offset = self.scroll.widget().offset();
     }
 
-    fn lifecycle(
-        &mut self,
-        ctx: &mut LifeCycleCtx,
-        event: &LifeCycle,
-        data: &crate::app::AppState,
-        env: &Env,
-    ) {
-        self.textbox.lifecycle(ctx, event, &data.text, env);
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &Document, env: &Env) {
+        self.scroll.lifecycle(ctx, event, data, env);
     }
 
-    fn update(
-        &mut self,
-        ctx: &mut UpdateCtx,
-        old_data: &crate::app::AppState,
-        data: &crate::app::AppState,
-        env: &Env,
-    ) {
-        if !old_data.text.same(&data.text) {
-            self.textbox.update(ctx, &data.text, env);
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Document, data: &Document, env: &Env) {
+        self.scroll.update(ctx, data, env);
+        if ctx.env_key_changed(&SHOW_LINE_NUMBERS) || !old_data.text.same(&data.text) {
+            ctx.request_layout();
+        }
+        if ctx.env_key_changed(&WRAP_COLUMN) {
+            ctx.request_layout();
+        }
+        if ctx.env_key_changed(&HIGHLIGHT_CURRENT_LINE)
+            || ctx.env_key_changed(&SHOW_WHITESPACE)
+            || ctx.env_key_changed(&RULERS)
+        {
+            ctx.request_paint();
         }
     }
 
@@ -149,16 +261,42 @@ impl Widget<crate::app::AppState> for EditorWidget {
         &mut self,
         ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        data: &crate::app::AppState,
+        data: &Document,
         env: &Env,
     ) -> Size {
-        let size = self.textbox.layout(ctx, bc, &data.text, env);
-        self.textbox.set_origin(ctx, Point::ORIGIN);
-        size
+        self.gutter
+            .measure(ctx, &data.text, env.get(SHOW_LINE_NUMBERS), env);
+        let text_bc = bc.shrink((self.gutter.width, 0.0));
+        let size = self.scroll.layout(ctx, &text_bc, data, env);
+        self.scroll
+            .set_origin(ctx, Point::new(self.gutter.width, 0.0));
+        if self.restore_scroll {
+            self.restore_scroll = false;
+            let delta: Vec2 = data.scroll_offset - self.scroll.widget().offset();
+            self.scroll.widget_mut().scroll_by(ctx, delta);
+        }
+        if self.follow_caret {
+            self.follow_caret = false;
+            if let Some(caret) = self.scroll.widget().child().caret_rect() {
+                self.scroll
+                    .widget_mut()
+                    .scroll_to(ctx, caret.inflate(0.0, 4.0));
+            }
+        }
+        Size::new(size.width + self.gutter.width, size.height)
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &crate::app::AppState, env: &Env) {
-        self.textbox.paint(ctx, &data.text, env);
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Document, env: &Env) {
+        self.scroll.paint(ctx, data, env);
+        let scroll_y = self.scroll.widget().offset().y;
+        self.gutter.paint(
+            ctx,
+            self.body(),
+            &data.text,
+            data.metrics.line,
+            scroll_y,
+            env,
+        );
     }
 }
 
@@ -181,3 +319,11 @@ fn column_from_bytes(text: &str, byte_index: usize) -> usize {
         .map(|(_, tail)| tail.chars().count() + 1)
         .unwrap_or_else(|| slice.chars().count() + 1)
 }
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
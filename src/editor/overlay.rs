@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use druid::kurbo::{Line, Point, Rect};
+use druid::piet::{PietTextLayout, Text, TextAttribute, TextLayout, TextLayoutBuilder};
+use druid::widget::prelude::*;
+use druid::{Color, Key, theme};
+
+use crate::theme::{BRACKET_MATCH, CURRENT_LINE, GUTTER_TEXT, RULER, WHITESPACE};
+
+use super::EDITOR_FONT_KEY;
+
+const SPACE_GLYPH: &str = "\u{b7}";
+const TAB_GLYPH: &str = "\u{2192}";
+const NEWLINE_GLYPH: &str = "\u{b6}";
+const FOLD_GLYPH: &str = "\u{22ef}";
+
+/// Fills the band behind the caret's line, across the whole width.
+pub fn paint_current_line(ctx: &mut PaintCtx, top: f64, bottom: f64, env: &Env) {
+    let width = ctx.size().width;
+    ctx.fill(Rect::new(0.0, top, width, bottom), &env.get(CURRENT_LINE));
+}
+
+/// Marks one half of a matched bracket pair.
+pub fn paint_bracket(ctx: &mut PaintCtx, rect: Rect, env: &Env) {
+    ctx.fill(rect.to_rounded_rect(2.0), &env.get(BRACKET_MATCH));
+}
+
+/// Fills the selected area of an extra caret.
+pub fn paint_selection(ctx: &mut PaintCtx, rect: Rect, env: &Env) {
+    ctx.fill(rect, &env.get(theme::SELECTED_TEXT_BACKGROUND_COLOR));
+}
+
+/// Draws an extra caret; the text box draws (and blinks) its own.
+pub fn paint_caret(ctx: &mut PaintCtx, rect: Rect, env: &Env) {
+    let x = rect.x0.round() + 0.5;
+    ctx.stroke(
+        Line::new((x, rect.y0), (x, rect.y1)),
+        &env.get(theme::CURSOR_COLOR),
+        1.0,
+    );
+}
+
+/// The advance of one character of the editor font. Exact for monospace
+/// fonts and an average-ish "0" width for the rest.
+pub fn column_width<T: Text>(text: &mut T, env: &Env) -> f64 {
+    glyph_layout(text, "0", WHITESPACE, env).size().width
+}
+
+/// Draws a vertical line at each ruler column; `left` is where column 0 starts.
+pub fn paint_rulers(ctx: &mut PaintCtx, left: f64, column_width: f64, rulers: &[u16], env: &Env) {
+    let height = ctx.size().height;
+    let color = env.get(RULER);
+    for &column in rulers {
+        let x = (left + f64::from(column) * column_width).round() + 0.5;
+        ctx.stroke(Line::new((x, 0.0), (x, height)), &color, 1.0);
+    }
+}
+
+/// Marks a folded block with an ellipsis box after its first line, which
+/// ends at `offset`.
+pub fn paint_fold_placeholder(
+    ctx: &mut PaintCtx,
+    layout: &PietTextLayout,
+    origin: Point,
+    offset: usize,
+    env: &Env,
+) {
+    let hit = layout.hit_test_text_position(offset);
+    let Some(line) = layout.line_metric(hit.line) else {
+        return;
+    };
+    let glyph = glyph_layout(ctx.text(), FOLD_GLYPH, GUTTER_TEXT, env);
+    let size = glyph.size();
+    let x = origin.x + hit.point.x + size.width / 2.0;
+    let top = origin.y + line.y_offset;
+    let rect = Rect::new(x, top + 1.0, x + size.width * 2.0, top + line.height - 1.0);
+    ctx.stroke(rect.to_rounded_rect(3.0), &env.get(GUTTER_TEXT), 1.0);
+    let ascent = glyph.line_metric(0).map_or(0.0, |metric| metric.baseline);
+    ctx.draw_text(
+        &glyph,
+        (
+            x + size.width / 2.0,
+            origin.y + line.y_offset + line.baseline - ascent,
+        ),
+    );
+}
+
+/// Draws a faint glyph over every space, tab and line break that falls in
+/// the area being repainted, except inside `folds`. `origin` is where the
+/// layout is drawn.
+pub fn paint_whitespace(
+    ctx: &mut PaintCtx,
+    layout: &PietTextLayout,
+    origin: Point,
+    text: &str,
+    folds: &[Range<usize>],
+    env: &Env,
+) {
+    let region = ctx.region().bounding_box();
+    let space = glyph_layout(ctx.text(), SPACE_GLYPH, WHITESPACE, env);
+    let tab = glyph_layout(ctx.text(), TAB_GLYPH, WHITESPACE, env);
+    let newline = glyph_layout(ctx.text(), NEWLINE_GLYPH, WHITESPACE, env);
+
+    for index in 0..layout.line_count() {
+        let Some(line) = layout.line_metric(index) else {
+            continue;
+        };
+        let top = origin.y + line.y_offset;
+        if top + line.height < region.y0 || top > region.y1 {
+            continue;
+        }
+        let baseline = origin.y + line.y_offset + line.baseline;
+        let end = line.end_offset.min(text.len());
+        for (offset, ch) in text[line.start_offset..end].char_indices() {
+            let offset = line.start_offset + offset;
+            if folds.iter().any(|fold| fold.contains(&offset)) {
+                continue;
+            }
+            let glyph = match ch {
+                ' ' => &space,
+                '\t' => &tab,
+                '\n' => &newline,
+                _ => continue,
+            };
+            let x = origin.x + layout.hit_test_text_position(offset).point.x;
+            // Spaces are centred on the gap they fill; the rest start at it.
+            let x = if ch == ' ' {
+                let next = origin.x + layout.hit_test_text_position(offset + 1).point.x;
+                x + ((next - x).max(glyph.size().width) - glyph.size().width) / 2.0
+            } else {
+                x
+            };
+            let ascent = glyph.line_metric(0).map_or(0.0, |metric| metric.baseline);
+            ctx.draw_text(glyph, (x, baseline - ascent));
+        }
+    }
+}
+
+fn glyph_layout<T: Text>(text: &mut T, glyph: &str, color: Key<Color>, env: &Env) -> T::TextLayout {
+    let font = env.get(EDITOR_FONT_KEY);
+    text.new_text_layout(glyph.to_string())
+        .font(font.family.clone(), font.size)
+        .default_attribute(TextAttribute::Weight(font.weight))
+        .text_color(env.get(color))
+        .build()
+        .expect("laying out a glyph cannot fail")
+}
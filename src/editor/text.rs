@@ -0,0 +1,111 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use druid::piet::{PietTextLayoutBuilder, TextAttribute, TextLayoutBuilder};
+use druid::text::{EditableText, StringCursor, TextStorage};
+use druid::{Color, Data, Env};
+
+/// Folded text is kept in the layout but shrunk to nothing, so every offset
+/// still maps to a position and the text box needs no translation layer.
+const HIDDEN_FONT_SIZE: f64 = 0.01;
+
+/// What the editor's text box edits: the document text plus the ranges that
+/// are folded away.
+#[derive(Clone, Default)]
+pub struct EditorText {
+    pub text: String,
+    pub folds: Arc<Vec<Range<usize>>>,
+}
+
+impl Data for EditorText {
+    fn same(&self, other: &Self) -> bool {
+        self.text.same(&other.text) && Arc::ptr_eq(&self.folds, &other.folds)
+    }
+}
+
+/// Hides `folds` in a layout of the text.
+pub fn hide_folds(
+    mut builder: PietTextLayoutBuilder,
+    folds: &[Range<usize>],
+) -> PietTextLayoutBuilder {
+    for fold in folds {
+        builder = builder
+            .range_attribute(fold.clone(), TextAttribute::FontSize(HIDDEN_FONT_SIZE))
+            .range_attribute(fold.clone(), TextAttribute::TextColor(Color::TRANSPARENT));
+    }
+    builder
+}
+
+impl druid::piet::TextStorage for EditorText {
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl TextStorage for EditorText {
+    fn add_attributes(&self, builder: PietTextLayoutBuilder, _env: &Env) -> PietTextLayoutBuilder {
+        hide_folds(builder, &self.folds)
+    }
+}
+
+impl EditableText for EditorText {
+    fn cursor(&self, position: usize) -> Option<StringCursor<'_>> {
+        self.text.cursor(position)
+    }
+
+    fn edit(&mut self, range: Range<usize>, new: impl Into<String>) {
+        self.text.edit(range, new);
+    }
+
+    fn slice(&self, range: Range<usize>) -> Option<Cow<'_, str>> {
+        self.text.slice(range)
+    }
+
+    fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    fn prev_word_offset(&self, offset: usize) -> Option<usize> {
+        self.text.prev_word_offset(offset)
+    }
+
+    fn next_word_offset(&self, offset: usize) -> Option<usize> {
+        self.text.next_word_offset(offset)
+    }
+
+    fn prev_grapheme_offset(&self, offset: usize) -> Option<usize> {
+        self.text.prev_grapheme_offset(offset)
+    }
+
+    fn next_grapheme_offset(&self, offset: usize) -> Option<usize> {
+        self.text.next_grapheme_offset(offset)
+    }
+
+    fn prev_codepoint_offset(&self, offset: usize) -> Option<usize> {
+        self.text.prev_codepoint_offset(offset)
+    }
+
+    fn next_codepoint_offset(&self, offset: usize) -> Option<usize> {
+        self.text.next_codepoint_offset(offset)
+    }
+
+    fn preceding_line_break(&self, offset: usize) -> usize {
+        self.text.preceding_line_break(offset)
+    }
+
+    fn next_line_break(&self, offset: usize) -> usize {
+        self.text.next_line_break(offset)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn from_str(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            folds: Arc::default(),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use druid::Data;
+
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// A block that can be collapsed onto its first line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoldRegion {
+    /// Byte offset where the first (still visible) line starts.
+    pub line: usize,
+    /// What folding hides: from the first line's line break to the end of the
+    /// block's last line, which keeps its own line break.
+    pub hidden: Range<usize>,
+}
+
+struct Line {
+    start: usize,
+    end: usize,
+    /// `None` for blank lines, which never end or start a block.
+    indent: Option<usize>,
+}
+
+/// Every foldable block of `text`, in order of first line. A block is the run
+/// of lines indented deeper than the line before it, or the lines between a
+/// bracket that ends a line and its partner; when both apply to the same
+/// line the larger wins. Brackets inside `literals` are ignored, and tabs
+/// count as `tab_width` columns.
+pub fn regions(text: &str, literals: &[Range<usize>], tab_width: usize) -> Vec<FoldRegion> {
+    let lines = split_lines(text, tab_width);
+    // First line index to last line index.
+    let mut blocks = BTreeMap::new();
+    let mut extend = |first: usize, last: usize| {
+        if last > first {
+            let entry = blocks.entry(first).or_insert(last);
+            *entry = (*entry).max(last);
+        }
+    };
+
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_content = 0;
+    for (index, line) in lines.iter().enumerate() {
+        let Some(indent) = line.indent else {
+            continue;
+        };
+        while let Some(&(depth, first)) = open.last()
+            && depth >= indent
+        {
+            open.pop();
+            extend(first, last_content);
+        }
+        open.push((indent, index));
+        last_content = index;
+    }
+    for (_, first) in open {
+        extend(first, last_content);
+    }
+
+    for (first, last) in bracket_blocks(text, &lines, literals) {
+        extend(first, last);
+    }
+
+    blocks
+        .into_iter()
+        .map(|(first, last)| FoldRegion {
+            line: lines[first].start,
+            hidden: lines[first].end..lines[last].end,
+        })
+        .collect()
+}
+
+fn split_lines(text: &str, tab_width: usize) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for line in text.split('\n') {
+        let end = start + line.len();
+        let content = line.trim_start();
+        let indent = (!content.trim_end().is_empty()).then(|| {
+            line[..line.len() - content.len()]
+                .chars()
+                .map(|ch| if ch == '\t' { tab_width } else { 1 })
+                .sum()
+        });
+        lines.push(Line { start, end, indent });
+        start = end + 1;
+    }
+    lines
+}
+
+/// Line pairs (first, last) for brackets that end a line and close on a later
+/// one. The closing line stays visible when it starts with the bracket.
+fn bracket_blocks(text: &str, lines: &[Line], literals: &[Range<usize>]) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    let mut literal = literals.iter().peekable();
+    for (index, line) in lines.iter().enumerate() {
+        for (offset, ch) in text[line.start..line.end].char_indices() {
+            let offset = line.start + offset;
+            while literal.next_if(|range| range.end <= offset).is_some() {}
+            if literal.peek().is_some_and(|range| range.start <= offset) {
+                continue;
+            }
+            if let Some(&(_, close)) = PAIRS.iter().find(|(open, _)| *open == ch) {
+                let ends_line = text[offset + 1..line.end].trim().is_empty();
+                stack.push((close, if ends_line { index } else { usize::MAX }));
+            } else if PAIRS.iter().any(|(_, close)| *close == ch) {
+                // Unbalanced closers are skipped rather than unwinding the stack.
+                if stack.last().is_some_and(|&(close, _)| close == ch)
+                    && let Some((_, first)) = stack.pop()
+                    && first != usize::MAX
+                {
+                    let leads = text[line.start..offset].trim().is_empty();
+                    blocks.push((first, if leads { index - 1 } else { index }));
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// A document's folded ranges, kept with the text they were made for so they
+/// can follow edits made while the editor showed another tab.
+#[derive(Clone, Data, Debug, Default)]
+pub struct Folds {
+    pub ranges: Arc<Vec<Range<usize>>>,
+    text: Arc<String>,
+}
+
+impl Folds {
+    pub fn new(ranges: Vec<Range<usize>>, text: &str) -> Self {
+        Self {
+            ranges: Arc::new(ranges),
+            text: Arc::new(text.to_string()),
+        }
+    }
+
+    /// The folds `keep` accepts, still for the same text.
+    pub fn filter(&self, keep: impl FnMut(&&Range<usize>) -> bool) -> Self {
+        Self {
+            ranges: Arc::new(self.ranges.iter().filter(keep).cloned().collect()),
+            text: self.text.clone(),
+        }
+    }
+
+    /// The folds carried across to `text`.
+    pub fn follow(&self, text: &str) -> Self {
+        if self.ranges.is_empty() || *self.text == text {
+            return self.clone();
+        }
+        Self::new(remap(&self.ranges, &self.text, text), text)
+    }
+}
+
+/// Carries folds across an edit from `old` to `new`: folds before the change
+/// stay, folds after it move with the text, and folds it touches open.
+pub fn remap(folds: &[Range<usize>], old: &str, new: &str) -> Vec<Range<usize>> {
+    let prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old.as_bytes()[prefix..]
+        .iter()
+        .rev()
+        .zip(new.as_bytes()[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    folds
+        .iter()
+        .filter_map(|fold| {
+            if fold.end < prefix {
+                Some(fold.clone())
+            } else if fold.start >= old_end {
+                Some(fold.start - old_end + new_end..fold.end - old_end + new_end)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use std::sync::OnceLock;
+
+use druid::text::FontFamily;
+
+/// Generic families every platform can resolve; always offered and used
+/// when a saved family is no longer installed.
+pub const GENERIC_FAMILIES: [&str; 3] = ["monospace", "sans-serif", "serif"];
+pub const FALLBACK_FAMILY: &str = "monospace";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledFamily {
+    pub name: String,
+    pub monospace: bool,
+}
+
+struct FontList {
+    families: Vec<InstalledFamily>,
+    /// Whether the platform listed its fonts; without that only the generic
+    /// families are known.
+    enumerated: bool,
+}
+
+static FONTS: OnceLock<FontList> = OnceLock::new();
+
+/// Installed font families, monospace ones first, then alphabetical. The
+/// list is read once per run, from fontconfig where available; `None` until
+/// `load_in_background` has read it.
+pub fn installed_families() -> Option<&'static [InstalledFamily]> {
+    FONTS.get().map(|fonts| fonts.families.as_slice())
+}
+
+/// Reads the family list on a worker thread and calls `done` once it is
+/// ready, so the UI thread never waits on fontconfig.
+pub fn load_in_background(done: impl FnOnce() + Send + 'static) {
+    std::thread::spawn(move || {
+        FONTS.get_or_init(load);
+        done();
+    });
+}
+
+/// Whether `name` can be used. Until the list has been read, or where the
+/// platform offers no way to list fonts, every name is trusted.
+pub fn is_installed(name: &str) -> bool {
+    match FONTS.get() {
+        Some(fonts) if fonts.enumerated => fonts.families.iter().any(|family| family.name == name),
+        _ => true,
+    }
+}
+
+/// Maps a family name to a druid family, recognising the generic names and
+/// showing `FALLBACK_FAMILY` in place of one that is not installed.
+pub fn family(name: &str) -> FontFamily {
+    match name {
+        "monospace" => FontFamily::MONOSPACE,
+        "sans-serif" => FontFamily::SANS_SERIF,
+        "serif" => FontFamily::SERIF,
+        _ if !is_installed(name) => family(FALLBACK_FAMILY),
+        _ => FontFamily::new_unchecked(name),
+    }
+}
+
+fn load() -> FontList {
+    let listed = query_fontconfig();
+    let enumerated = listed.is_some();
+    let mut families = listed.unwrap_or_default();
+    for name in GENERIC_FAMILIES {
+        families.push(InstalledFamily {
+            name: name.to_string(),
+            monospace: name == FALLBACK_FAMILY,
+        });
+    }
+    families.sort_by(|a, b| {
+        b.monospace
+            .cmp(&a.monospace)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    families.dedup_by(|a, b| a.name == b.name);
+    FontList {
+        families,
+        enumerated,
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn query_fontconfig() -> Option<Vec<InstalledFamily>> {
+    None
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn query_fontconfig() -> Option<Vec<InstalledFamily>> {
+    use std::collections::BTreeMap;
+    use std::process::Command;
+
+    let output = Command::new("fc-list")
+        .args([":", "--format", "%{family[0]}\t%{spacing}\n"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // A family is monospace if any of its faces reports a fixed spacing.
+    let mut families: BTreeMap<String, bool> = BTreeMap::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (name, spacing) = line.split_once('\t').unwrap_or((line, ""));
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        // fontconfig spacing: 100 = mono, 110 = charcell.
+        let monospace = matches!(spacing.trim(), "100" | "110");
+        *families.entry(name.to_string()).or_default() |= monospace;
+    }
+    Some(
+        families
+            .into_iter()
+            .map(|(name, monospace)| InstalledFamily { name, monospace })
+            .collect(),
+    )
+}
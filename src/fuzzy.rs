@@ -0,0 +1,32 @@
+/// Scores `candidate` against `query` as a case-insensitive subsequence
+/// match; `None` when some query character does not appear in order.
+/// Matches at word starts and runs of adjacent characters score higher.
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut chars = candidate.chars().enumerate().peekable();
+    let mut previous: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+    for wanted in query.chars().filter(|ch| !ch.is_whitespace()) {
+        loop {
+            let (index, ch) = chars.next()?;
+            let word_start = previous.is_none_or(|prev| {
+                !prev.is_alphanumeric() || (prev.is_lowercase() && ch.is_uppercase())
+            });
+            previous = Some(ch);
+            if !ch.to_lowercase().eq(wanted.to_lowercase()) {
+                score -= 1;
+                continue;
+            }
+            score += 2;
+            if word_start {
+                score += 10;
+            }
+            if last_match.is_some_and(|last| last + 1 == index) {
+                score += 5;
+            }
+            last_match = Some(index);
+            break;
+        }
+    }
+    Some(score)
+}
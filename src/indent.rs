@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::lines::{self, Edit};
+
+/// How a document is indented, as found by `detect`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tabs,
+    /// Spaces, this many per level.
+    Spaces(usize),
+}
+
+/// The indentation the editor types: tabs of `width` columns, or `width`
+/// spaces per level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Indentation {
+    pub tabs: bool,
+    pub width: usize,
+}
+
+impl Indentation {
+    pub fn unit(&self) -> String {
+        if self.tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.width)
+        }
+    }
+
+    /// What Tab inserts after `prefix`, the part of the line before the caret:
+    /// a tab, or spaces up to the next tab stop.
+    pub fn fill(&self, prefix: &str) -> String {
+        if self.tabs {
+            return "\t".to_string();
+        }
+        let column = visual_width(prefix, self.width);
+        " ".repeat(self.width - column % self.width)
+    }
+}
+
+/// Guesses the indentation of `text` from the lines that are indented: tabs
+/// when most start with one, otherwise the most common step between a line
+/// and a deeper one. `None` when nothing is indented.
+pub fn detect(text: &str) -> Option<IndentStyle> {
+    let mut tabs = 0;
+    let mut spaces = 0;
+    let mut steps: HashMap<usize, usize> = HashMap::new();
+    let mut previous = 0;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start().len()];
+        if indent.starts_with('\t') {
+            tabs += 1;
+        } else if indent.starts_with(' ') {
+            spaces += 1;
+        }
+        if !indent.contains('\t') {
+            let depth = indent.len();
+            if depth > previous && depth - previous <= 8 {
+                *steps.entry(depth - previous).or_default() += 1;
+            }
+            previous = depth;
+        }
+    }
+    if tabs == 0 && spaces == 0 {
+        return None;
+    }
+    if tabs > spaces {
+        return Some(IndentStyle::Tabs);
+    }
+    // Ties go to the smaller step, which the larger ones are usually made of.
+    let width = steps
+        .into_iter()
+        .max_by_key(|&(step, count)| (count, std::cmp::Reverse(step)))
+        .map(|(step, _)| step)?;
+    Some(IndentStyle::Spaces(width))
+}
+
+/// Adds one level of indentation to every non-blank line the selection
+/// touches. The selection grows to keep covering the same text.
+pub fn indent_lines(text: &str, selection: Range<usize>, indentation: Indentation) -> Edit {
+    let unit = indentation.unit();
+    let mut changes = Vec::new();
+    for line in lines::touched_line_starts(text, &selection) {
+        let end = text[line..]
+            .find('\n')
+            .map_or(text.len(), |index| line + index);
+        if !text[line..end].trim().is_empty() {
+            changes.push((line..line, unit.as_str()));
+        }
+    }
+    lines::splice(text, selection, &changes)
+}
+
+/// Removes one level of indentation from every line the selection touches:
+/// a leading tab, or up to `width` leading spaces. `None` when no line is
+/// indented.
+pub fn outdent_lines(
+    text: &str,
+    selection: Range<usize>,
+    indentation: Indentation,
+) -> Option<Edit> {
+    let mut changes = Vec::new();
+    for line in lines::touched_line_starts(text, &selection) {
+        let rest = &text[line..];
+        let removed = if rest.starts_with('\t') {
+            1
+        } else {
+            rest.bytes()
+                .take(indentation.width)
+                .take_while(|&byte| byte == b' ')
+                .count()
+        };
+        if removed > 0 {
+            changes.push((line..line + removed, ""));
+        }
+    }
+    (!changes.is_empty()).then(|| lines::splice(text, selection, &changes))
+}
+
+/// Rewrites the leading whitespace of every line with tabs, or with spaces,
+/// keeping its width. Spaces short of a whole tab stay as spaces. Returns
+/// the new text and where `caret` moves to: the same place in its line's
+/// text, or the end of the indentation when it was inside it.
+pub fn convert(text: &str, indentation: Indentation, caret: usize) -> (String, usize) {
+    let mut result = String::with_capacity(text.len());
+    let mut moved = caret;
+    let mut start = 0;
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+        let content = line.trim_start_matches([' ', '\t']);
+        let old_indent = line.len() - content.len();
+        let width = visual_width(&line[..old_indent], indentation.width);
+        if indentation.tabs {
+            result.push_str(&"\t".repeat(width / indentation.width));
+            result.push_str(&" ".repeat(width % indentation.width));
+        } else {
+            result.push_str(&" ".repeat(width));
+        }
+        if (start..=start + line.len()).contains(&caret) {
+            moved = result.len() + caret.saturating_sub(start + old_indent);
+        }
+        result.push_str(content);
+        start += line.len() + 1;
+    }
+    (result, moved)
+}
+
+/// Columns `text` takes up, with tabs advancing to the next multiple of
+/// `tab_width`.
+fn visual_width(text: &str, tab_width: usize) -> usize {
+    text.chars().fold(0, |column, ch| {
+        if ch == '\t' {
+            column + tab_width - column % tab_width
+        } else {
+            column + 1
+        }
+    })
+}
@@ -0,0 +1,372 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use druid::keyboard_types::{Key, Modifiers};
+use druid::{HotKey, KeyEvent, RawMods};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::paths;
+use crate::storage::{self, StorageError};
+
+/// Longest supported chord sequence, e.g. `Ctrl+K Ctrl+C`.
+const MAX_CHORDS: usize = 2;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeymapError {
+    #[error("\"{0}\" is not a key")]
+    UnknownKey(String),
+    #[error("\"{0}\" has no key after its modifiers")]
+    MissingKey(String),
+    #[error("\"{0}\" has more than {MAX_CHORDS} chords")]
+    TooManyChords(String),
+}
+
+/// One key press together with the modifiers held for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chord {
+    mods: Modifiers,
+    key: Key,
+}
+
+impl Chord {
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self {
+            mods: event.mods.raw() & chord_modifiers(),
+            key: event.key.clone(),
+        }
+    }
+
+    /// Modifier keys on their own never complete a chord.
+    pub fn is_modifier_only(&self) -> bool {
+        matches!(
+            self.key,
+            Key::Control | Key::Shift | Key::Alt | Key::AltGraph | Key::Meta | Key::Super
+        )
+    }
+
+    pub fn hotkey(&self) -> HotKey {
+        HotKey::new(raw_mods(self.mods), self.key.clone())
+    }
+}
+
+impl FromStr for Chord {
+    type Err = KeymapError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut mods = Modifiers::empty();
+        let mut key = None;
+        // A trailing "+" is the plus key itself, as in "Ctrl++".
+        let mut parts: Vec<&str> = text.split('+').collect();
+        if text.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        for part in parts {
+            if key.is_some() {
+                return Err(KeymapError::UnknownKey(text.to_string()));
+            }
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => mods |= Modifiers::CONTROL,
+                "shift" => mods |= Modifiers::SHIFT,
+                "alt" | "option" => mods |= Modifiers::ALT,
+                "meta" | "super" | "win" => mods |= Modifiers::META,
+                "cmd" | "mod" => mods |= command_modifier(),
+                _ => {
+                    key = Some(parse_key(part).ok_or_else(|| KeymapError::UnknownKey(text.into()))?)
+                }
+            }
+        }
+        let mut key = key.ok_or_else(|| KeymapError::MissingKey(text.to_string()))?;
+        // Shifted letters arrive as upper case characters.
+        if let Key::Character(ch) = &key {
+            key = Key::Character(if mods.contains(Modifiers::SHIFT) {
+                ch.to_uppercase()
+            } else {
+                ch.to_lowercase()
+            });
+        }
+        Ok(Self { mods, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (flag, name) in [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (
+                Modifiers::META,
+                if cfg!(target_os = "macos") {
+                    "Cmd"
+                } else {
+                    "Super"
+                },
+            ),
+        ] {
+            if self.mods.contains(flag) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.key {
+            Key::Character(ch) if ch == " " => f.write_str("Space"),
+            Key::Character(ch) => f.write_str(&ch.to_uppercase()),
+            Key::ArrowUp => f.write_str("Up"),
+            Key::ArrowDown => f.write_str("Down"),
+            Key::ArrowLeft => f.write_str("Left"),
+            Key::ArrowRight => f.write_str("Right"),
+            key => write!(f, "{key}"),
+        }
+    }
+}
+
+/// A sequence of one or more chords that triggers a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding(Vec<Chord>);
+
+impl Binding {
+    pub fn chords(&self) -> &[Chord] {
+        &self.0
+    }
+
+    /// Either binding would fire (or wait for more keys) when the other is
+    /// typed, or a later chord of one is the other's single key, which its
+    /// menu accelerator takes before the sequence can finish.
+    fn overlaps(&self, other: &Binding) -> bool {
+        let len = self.0.len().min(other.0.len());
+        let shadows = |sequence: &Binding, single: &Binding| match single.0.as_slice() {
+            [chord] => sequence.0[1..].contains(chord),
+            _ => false,
+        };
+        self.0[..len] == other.0[..len] || shadows(self, other) || shadows(other, self)
+    }
+}
+
+impl FromStr for Binding {
+    type Err = KeymapError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let chords = text
+            .split_whitespace()
+            .map(Chord::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if chords.len() > MAX_CHORDS {
+            return Err(KeymapError::TooManyChords(text.to_string()));
+        }
+        if chords.is_empty() {
+            return Err(KeymapError::MissingKey(text.to_string()));
+        }
+        Ok(Self(chords))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, chord) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What a sequence of key presses resolves to.
+#[derive(Debug, PartialEq, Eq)]
+pub enum KeyMatch {
+    None,
+    /// A prefix of at least one binding; wait for the next chord.
+    Pending,
+    Command(&'static str),
+}
+
+/// Key bindings by command id, after user overrides have been applied.
+#[derive(Debug, Default)]
+pub struct Keymap {
+    bindings: Vec<(&'static str, Binding)>,
+}
+
+impl Keymap {
+    /// Starts from `defaults` and applies `overrides` (command id to binding,
+    /// an empty string unbinds). Overrides that are malformed, name unknown
+    /// commands or clash with another binding fall back to the default, and
+    /// each is described in the returned list.
+    pub fn resolve(
+        defaults: &[(&'static str, &'static str)],
+        overrides: &BTreeMap<String, String>,
+    ) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let default_of = |id: &str| {
+            defaults
+                .iter()
+                .find(|(default_id, _)| *default_id == id)
+                .and_then(|(_, keys)| keys.parse::<Binding>().ok())
+        };
+        let mut entries: Vec<(&'static str, Option<Binding>, bool)> = defaults
+            .iter()
+            .map(|(id, _)| (*id, default_of(id), false))
+            .collect();
+
+        for (id, keys) in overrides {
+            let Some(entry) = entries.iter_mut().find(|(known, _, _)| known == id) else {
+                problems.push(format!("unknown command \"{id}\""));
+                continue;
+            };
+            if keys.trim().is_empty() {
+                *entry = (entry.0, None, true);
+                continue;
+            }
+            match keys.parse::<Binding>() {
+                Ok(binding) => *entry = (entry.0, Some(binding), true),
+                Err(err) => problems.push(format!("{id}: {err}")),
+            }
+        }
+
+        // Reverting one override can expose a clash with another, so repeat
+        // until only defaults are left in conflict (or nothing is).
+        loop {
+            let mut reverted = Vec::new();
+            for (index, (id, binding, overridden)) in entries.iter().enumerate() {
+                let Some(binding) = binding else {
+                    continue;
+                };
+                let clash = entries.iter().enumerate().find(|(other, (_, theirs, _))| {
+                    *other != index
+                        && theirs
+                            .as_ref()
+                            .is_some_and(|theirs| theirs.overlaps(binding))
+                });
+                if let (true, Some((_, (other_id, _, _)))) = (*overridden, clash) {
+                    problems.push(format!(
+                        "{binding} for {id} conflicts with {other_id}; keeping the default"
+                    ));
+                    reverted.push(index);
+                }
+            }
+            if reverted.is_empty() {
+                break;
+            }
+            for index in reverted {
+                let id = entries[index].0;
+                entries[index] = (id, default_of(id), false);
+            }
+        }
+
+        let bindings = entries
+            .into_iter()
+            .filter_map(|(id, binding, _)| Some((id, binding?)))
+            .collect();
+        (Self { bindings }, problems)
+    }
+
+    pub fn binding(&self, id: &str) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == id)
+            .map(|(_, binding)| binding)
+    }
+
+    /// The menu accelerator for `id`; only single-chord bindings have one.
+    pub fn hotkey(&self, id: &str) -> Option<HotKey> {
+        match self.binding(id)?.chords() {
+            [chord] => Some(chord.hotkey()),
+            _ => None,
+        }
+    }
+
+    pub fn lookup(&self, pressed: &[Chord]) -> KeyMatch {
+        let mut result = KeyMatch::None;
+        for (id, binding) in &self.bindings {
+            if binding.chords() == pressed {
+                return KeyMatch::Command(id);
+            }
+            if binding.chords().starts_with(pressed) {
+                result = KeyMatch::Pending;
+            }
+        }
+        result
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct KeymapFile {
+    bindings: BTreeMap<String, String>,
+}
+
+pub fn keymap_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("keymap.toml"))
+}
+
+/// Reads the user's overrides; empty when there is no keymap file.
+pub fn load_overrides() -> Result<BTreeMap<String, String>, StorageError> {
+    let path = keymap_path().ok_or(StorageError::NoDirectory)?;
+    Ok(storage::load_toml::<KeymapFile>(&path)?
+        .unwrap_or_default()
+        .bindings)
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "up" => Key::ArrowUp,
+        "down" => Key::ArrowDown,
+        "left" => Key::ArrowLeft,
+        "right" => Key::ArrowRight,
+        "esc" => Key::Escape,
+        "del" => Key::Delete,
+        "ins" => Key::Insert,
+        "pgup" => Key::PageUp,
+        "pgdn" => Key::PageDown,
+        "return" => Key::Enter,
+        "space" => Key::Character(" ".to_string()),
+        _ => match Key::from_str(name) {
+            Ok(Key::Character(ch)) if ch.chars().count() != 1 => return None,
+            Ok(key) => key,
+            Err(_) => return None,
+        },
+    };
+    Some(key)
+}
+
+fn chord_modifiers() -> Modifiers {
+    Modifiers::SHIFT | Modifiers::CONTROL | Modifiers::ALT | Modifiers::META
+}
+
+pub fn command_modifier() -> Modifiers {
+    if cfg!(target_os = "macos") {
+        Modifiers::META
+    } else {
+        Modifiers::CONTROL
+    }
+}
+
+fn raw_mods(mods: Modifiers) -> RawMods {
+    let has = |flag| mods.contains(flag);
+    match (
+        has(Modifiers::ALT),
+        has(Modifiers::CONTROL),
+        has(Modifiers::META),
+        has(Modifiers::SHIFT),
+    ) {
+        (false, false, false, false) => RawMods::None,
+        (true, false, false, false) => RawMods::Alt,
+        (false, true, false, false) => RawMods::Ctrl,
+        (false, false, true, false) => RawMods::Meta,
+        (false, false, false, true) => RawMods::Shift,
+        (true, true, false, false) => RawMods::AltCtrl,
+        (true, false, true, false) => RawMods::AltMeta,
+        (true, false, false, true) => RawMods::AltShift,
+        (false, true, false, true) => RawMods::CtrlShift,
+        (false, true, true, false) => RawMods::CtrlMeta,
+        (false, false, true, true) => RawMods::MetaShift,
+        (true, true, true, false) => RawMods::AltCtrlMeta,
+        (true, true, false, true) => RawMods::AltCtrlShift,
+        (true, false, true, true) => RawMods::AltMetaShift,
+        (false, true, true, true) => RawMods::CtrlMetaShift,
+        (true, true, true, true) => RawMods::AltCtrlMetaShift,
+    }
+}
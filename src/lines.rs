@@ -0,0 +1,575 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineOperation {
+    Duplicate,
+    MoveUp,
+    MoveDown,
+    Delete,
+    Join,
+    InsertAbove,
+    InsertBelow,
+    Sort { key: SortKey, descending: bool },
+    RemoveDuplicates,
+    RemoveBlank,
+    Shuffle,
+    Reverse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Text,
+    CaseInsensitive,
+    /// Runs of digits compare by value, so `file2` sorts before `file10`.
+    Natural,
+}
+
+/// One replacement, and what to select afterwards (in the new text).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub replacement: String,
+    pub selection: Range<usize>,
+}
+
+/// Applies `operation` to the lines touched by `selection`. `None` when there
+/// is nothing to do, such as moving the first line up.
+pub fn apply(text: &str, selection: Range<usize>, operation: LineOperation) -> Option<Edit> {
+    let lines = touched_lines(text, &selection);
+    match operation {
+        LineOperation::Duplicate => Some(duplicate(text, selection, lines)),
+        LineOperation::MoveUp => move_up(text, selection, lines),
+        LineOperation::MoveDown => move_down(text, selection, lines),
+        LineOperation::Delete => Some(delete(text, lines)),
+        LineOperation::Join => join(text, selection, lines),
+        LineOperation::InsertAbove => {
+            let indent = indent_of(&text[lines.start..]);
+            let caret = lines.start + indent.len();
+            Some(Edit {
+                range: lines.start..lines.start,
+                replacement: format!("{indent}\n"),
+                selection: caret..caret,
+            })
+        }
+        LineOperation::InsertBelow => {
+            let end = lines.end;
+            let indent = indent_of(&text[line_start(text, end)..]);
+            let caret = end + 1 + indent.len();
+            Some(Edit {
+                range: end..end,
+                replacement: format!("\n{indent}"),
+                selection: caret..caret,
+            })
+        }
+        LineOperation::Sort { key, descending } => rewrite_lines(text, selection, |lines| {
+            lines.sort_by(|a, b| order(compare(a, b, key), descending));
+        }),
+        LineOperation::RemoveDuplicates => rewrite_lines(text, selection, |lines| {
+            let mut seen = HashSet::new();
+            lines.retain(|line| seen.insert(*line));
+        }),
+        LineOperation::RemoveBlank => rewrite_lines(text, selection, |lines| {
+            lines.retain(|line| !line.trim().is_empty());
+        }),
+        LineOperation::Shuffle => rewrite_lines(text, selection, shuffle),
+        LineOperation::Reverse => rewrite_lines(text, selection, |lines| lines.reverse()),
+    }
+}
+
+/// Sorts by the `column`th field (counted from one) of each line, split at
+/// `delimiter` or, when it is empty, at runs of whitespace. Fields compare in
+/// natural order; lines without the field sort as if it were empty.
+pub fn sort_by_column(
+    text: &str,
+    selection: Range<usize>,
+    delimiter: &str,
+    column: usize,
+    descending: bool,
+) -> Option<Edit> {
+    let field = |line: &str| -> String {
+        let field = if delimiter.is_empty() {
+            line.split_whitespace().nth(column - 1)
+        } else {
+            line.split(delimiter).nth(column - 1)
+        };
+        field.unwrap_or_default().trim().to_string()
+    };
+    rewrite_lines(text, selection, |lines| {
+        let mut keyed: Vec<(String, &str)> =
+            lines.iter().map(|line| (field(line), *line)).collect();
+        keyed.sort_by(|a, b| order(natural_cmp(&a.0, &b.0), descending));
+        *lines = keyed.into_iter().map(|(_, line)| line).collect();
+    })
+}
+
+/// The whole lines `selection` touches, without the last line's newline. A
+/// selection ending at the very start of a line does not take that line.
+pub fn touched_lines(text: &str, selection: &Range<usize>) -> Range<usize> {
+    let end = if selection.end > selection.start && text[..selection.end].ends_with('\n') {
+        selection.end - 1
+    } else {
+        selection.end
+    };
+    line_start(text, selection.start)..line_end(text, end)
+}
+
+/// Where each line the selection touches starts.
+pub fn touched_line_starts(text: &str, selection: &Range<usize>) -> Vec<usize> {
+    let lines = touched_lines(text, selection);
+    std::iter::once(lines.start)
+        .chain(
+            text[lines.clone()]
+                .match_indices('\n')
+                .map(|(index, _)| lines.start + index + 1),
+        )
+        .collect()
+}
+
+/// Applies `changes` (sorted, non-overlapping replacements) as one edit over
+/// the lines they touch, moving the selection with the text around it. An
+/// end of the selection at the very start of a line stays there.
+pub fn splice(text: &str, selection: Range<usize>, changes: &[(Range<usize>, &str)]) -> Edit {
+    let start = changes
+        .first()
+        .map_or(selection.start, |(range, _)| range.start);
+    let end = changes
+        .last()
+        .map_or(selection.start, |(range, _)| range.end);
+    let mut replacement = String::new();
+    let mut copied = start;
+    for (range, value) in changes {
+        replacement.push_str(&text[copied..range.start]);
+        replacement.push_str(value);
+        copied = range.end;
+    }
+    replacement.push_str(&text[copied..end]);
+    let map = |offset: usize| {
+        changes.iter().fold(offset, |mapped, (range, value)| {
+            if offset <= range.start {
+                mapped
+            } else if offset < range.end {
+                mapped - (offset - range.start) + value.len()
+            } else {
+                mapped - range.len() + value.len()
+            }
+        })
+    };
+    Edit {
+        range: start..end,
+        replacement,
+        selection: map(selection.start)..map(selection.end),
+    }
+}
+
+fn duplicate(text: &str, selection: Range<usize>, lines: Range<usize>) -> Edit {
+    if selection.start != selection.end {
+        let copy = &text[selection.clone()];
+        return Edit {
+            range: selection.end..selection.end,
+            replacement: copy.to_string(),
+            selection: selection.end..selection.end + copy.len(),
+        };
+    }
+    // The copy goes below, and the caret follows it.
+    let block = &text[lines.clone()];
+    let shift = block.len() + 1;
+    Edit {
+        range: lines.end..lines.end,
+        replacement: format!("\n{block}"),
+        selection: selection.start + shift..selection.end + shift,
+    }
+}
+
+fn move_up(text: &str, selection: Range<usize>, lines: Range<usize>) -> Option<Edit> {
+    if lines.start == 0 {
+        return None;
+    }
+    let above = line_start(text, lines.start - 1);
+    let neighbour = &text[above..lines.start - 1];
+    let shift = neighbour.len() + 1;
+    Some(Edit {
+        range: above..lines.end,
+        replacement: format!("{}\n{neighbour}", &text[lines.clone()]),
+        selection: selection.start - shift..selection.end - shift,
+    })
+}
+
+fn move_down(text: &str, selection: Range<usize>, lines: Range<usize>) -> Option<Edit> {
+    if lines.end >= text.len() {
+        return None;
+    }
+    let below = line_end(text, lines.end + 1);
+    let neighbour = &text[lines.end + 1..below];
+    let shift = neighbour.len() + 1;
+    Some(Edit {
+        range: lines.start..below,
+        replacement: format!("{neighbour}\n{}", &text[lines.clone()]),
+        selection: selection.start + shift..selection.end + shift,
+    })
+}
+
+/// Removes the lines with their line break; the caret lands at the start of
+/// the line that moves up, or of the new last line.
+fn delete(text: &str, lines: Range<usize>) -> Edit {
+    if lines.end < text.len() {
+        return Edit {
+            range: lines.start..lines.end + 1,
+            replacement: String::new(),
+            selection: lines.start..lines.start,
+        };
+    }
+    let start = lines.start.saturating_sub(1);
+    let caret = line_start(text, start);
+    Edit {
+        range: start..lines.end,
+        replacement: String::new(),
+        selection: caret..caret,
+    }
+}
+
+/// Joins the selected lines, or the caret's line and the next, with single
+/// spaces in place of the line breaks and the indentation after them.
+fn join(text: &str, selection: Range<usize>, lines: Range<usize>) -> Option<Edit> {
+    let lines = if text[lines.clone()].contains('\n') {
+        lines
+    } else if lines.end < text.len() {
+        lines.start..line_end(text, lines.end + 1)
+    } else {
+        return None;
+    };
+    let mut joined = String::with_capacity(lines.len());
+    let mut caret = selection.start;
+    for (index, line) in text[lines.clone()].split('\n').enumerate() {
+        if index == 0 {
+            joined.push_str(line);
+            continue;
+        }
+        let line = line.trim_start();
+        if !line.is_empty() && !joined.is_empty() && !joined.ends_with(char::is_whitespace) {
+            joined.push(' ');
+        }
+        caret = lines.start + joined.len();
+        joined.push_str(line);
+    }
+    // A selection stays over the joined text; a caret goes to the last join.
+    let selection = if selection.start == selection.end {
+        caret..caret
+    } else {
+        lines.start..lines.start + joined.len()
+    };
+    Some(Edit {
+        range: lines,
+        replacement: joined,
+        selection,
+    })
+}
+
+/// Rewrites the lines the selection touches, or every line when nothing is
+/// selected, and selects the result.
+fn rewrite_lines(
+    text: &str,
+    selection: Range<usize>,
+    rewrite: impl FnOnce(&mut Vec<&str>),
+) -> Option<Edit> {
+    let range = if selection.start == selection.end {
+        // A final line break ends the last line rather than adding an empty one.
+        0..text.strip_suffix('\n').unwrap_or(text).len()
+    } else {
+        touched_lines(text, &selection)
+    };
+    let mut lines: Vec<&str> = text[range.clone()].split('\n').collect();
+    let count = lines.len();
+    rewrite(&mut lines);
+    let replacement = lines.join("\n");
+    if replacement == text[range.clone()] && lines.len() == count {
+        return None;
+    }
+    // Removing every line of a block also takes the line break after it.
+    let range = if lines.is_empty() && range.end < text.len() {
+        range.start..range.end + 1
+    } else {
+        range
+    };
+    let selection = if selection.start == selection.end {
+        range.start..range.start
+    } else {
+        range.start..range.start + replacement.len()
+    };
+    Some(Edit {
+        range,
+        replacement,
+        selection,
+    })
+}
+
+fn compare(a: &str, b: &str, key: SortKey) -> Ordering {
+    match key {
+        SortKey::Text => a.cmp(b),
+        SortKey::CaseInsensitive => a.to_lowercase().cmp(&b.to_lowercase()),
+        SortKey::Natural => natural_cmp(a, b),
+    }
+}
+
+/// Reverses `ordering` for a descending sort, keeping equal lines in place.
+fn order(ordering: Ordering, descending: bool) -> Ordering {
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Compares digit runs by numeric value and everything else case-insensitively,
+/// falling back to plain comparison so the order is total.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = digits(&mut left);
+                let y = digits(&mut right);
+                let x = x.trim_start_matches('0');
+                let y = y.trim_start_matches('0');
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+fn digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut run = String::new();
+    while let Some(ch) = chars.next_if(char::is_ascii_digit) {
+        run.push(ch);
+    }
+    run
+}
+
+/// Fisher-Yates with a xorshift generator seeded from the standard library's
+/// per-process random keys.
+fn shuffle(lines: &mut Vec<&str>) {
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+    for index in (1..lines.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        lines.swap(index, (state % (index as u64 + 1)) as usize);
+    }
+}
+
+fn indent_of(line: &str) -> &str {
+    let content = line.split('\n').next().unwrap_or_default();
+    &content[..content.len() - content.trim_start().len()]
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn line_end(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .find('\n')
+        .map_or(text.len(), |index| offset + index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text after `operation`, with the new selection marked by `[` and
+    /// `]` (or `|` for a caret).
+    fn run(text: &str, selection: Range<usize>, operation: LineOperation) -> Option<String> {
+        apply(text, selection, operation).map(|edit| render(text, &edit))
+    }
+
+    fn render(text: &str, edit: &Edit) -> String {
+        let mut result = text.to_string();
+        result.replace_range(edit.range.clone(), &edit.replacement);
+        let Range { start, end } = edit.selection;
+        if start == end {
+            result.insert(start, '|');
+        } else {
+            result.insert(end, ']');
+            result.insert(start, '[');
+        }
+        result
+    }
+
+    #[test]
+    fn touched_lines_cover_whole_lines() {
+        let text = "one\ntwo\nthree";
+        assert_eq!(touched_lines(text, &(5..5)), 4..7);
+        assert_eq!(touched_lines(text, &(0..0)), 0..3);
+        assert_eq!(touched_lines(text, &(13..13)), 8..13);
+        assert_eq!(touched_lines(text, &(1..10)), 0..13);
+    }
+
+    #[test]
+    fn selection_ending_at_column_zero_leaves_that_line() {
+        let text = "one\ntwo\nthree";
+        assert_eq!(touched_lines(text, &(0..4)), 0..3);
+        assert_eq!(touched_line_starts(text, &(0..8)), vec![0, 4]);
+        // A caret at the start of a line is on that line.
+        assert_eq!(touched_lines(text, &(4..4)), 4..7);
+    }
+
+    #[test]
+    fn splice_moves_the_selection_with_the_text() {
+        let text = "ab\ncd";
+        let edit = splice(text, 0..5, &[(0..0, "# "), (3..3, "# ")]);
+        assert_eq!(render(text, &edit), "[# ab\n# cd]");
+        // An end at an insertion point stays before the inserted text.
+        let edit = splice(text, 3..3, &[(3..3, "  ")]);
+        assert_eq!(render(text, &edit), "ab\n|  cd");
+        // A selection inside a replaced range ends up after the replacement.
+        let edit = splice(text, 4..5, &[(3..5, "x")]);
+        assert_eq!(render(text, &edit), "ab\nx|");
+    }
+
+    #[test]
+    fn splice_without_changes_keeps_the_selection() {
+        let edit = splice("abc", 1..2, &[]);
+        assert_eq!(edit.range, 1..1);
+        assert_eq!(edit.replacement, "");
+        assert_eq!(edit.selection, 1..2);
+    }
+
+    #[test]
+    fn move_up_and_down_stop_at_the_ends() {
+        let text = "a\nb\nc";
+        assert_eq!(run(text, 0..0, LineOperation::MoveUp), None);
+        assert_eq!(run(text, 4..4, LineOperation::MoveDown), None);
+        assert_eq!(
+            run(text, 2..2, LineOperation::MoveUp),
+            Some("|b\na\nc".into())
+        );
+        assert_eq!(
+            run(text, 2..2, LineOperation::MoveDown),
+            Some("a\nc\n|b".into())
+        );
+    }
+
+    #[test]
+    fn move_down_carries_a_selection() {
+        let text = "a\nb\nc\nd";
+        assert_eq!(
+            run(text, 0..3, LineOperation::MoveDown),
+            Some("c\n[a\nb]\nd".into())
+        );
+        // The line after a selection ending at column zero is not moved.
+        assert_eq!(
+            run(text, 0..2, LineOperation::MoveDown),
+            Some("b\n[a\n]c\nd".into())
+        );
+    }
+
+    #[test]
+    fn delete_takes_the_line_break() {
+        let text = "a\nb\nc";
+        assert_eq!(run(text, 0..0, LineOperation::Delete), Some("|b\nc".into()));
+        assert_eq!(run(text, 4..4, LineOperation::Delete), Some("a\n|b".into()));
+        assert_eq!(run("a", 0..1, LineOperation::Delete), Some("|".into()));
+    }
+
+    #[test]
+    fn join_uses_single_spaces() {
+        let text = "fn f() {\n    body\n}";
+        assert_eq!(
+            run(text, 0..0, LineOperation::Join),
+            Some("fn f() { |body\n}".into())
+        );
+        assert_eq!(
+            run(text, 0..text.len(), LineOperation::Join),
+            Some("[fn f() { body }]".into())
+        );
+        assert_eq!(run(text, 18..18, LineOperation::Join), None);
+    }
+
+    #[test]
+    fn join_skips_blank_lines() {
+        assert_eq!(
+            run("a\n\nb", 0..4, LineOperation::Join),
+            Some("[a b]".into())
+        );
+        assert_eq!(run("a \nb", 0..0, LineOperation::Join), Some("a |b".into()));
+    }
+
+    #[test]
+    fn whole_document_keeps_its_final_line_break() {
+        let sort = LineOperation::Sort {
+            key: SortKey::Text,
+            descending: false,
+        };
+        assert_eq!(run("b\na\n", 0..0, sort), Some("|a\nb\n".into()));
+        assert_eq!(run("a\nb\n", 0..0, sort), None);
+        assert_eq!(
+            run("b\na\n", 0..0, LineOperation::Reverse),
+            Some("|a\nb\n".into())
+        );
+    }
+
+    #[test]
+    fn removing_every_line() {
+        // The whole document.
+        assert_eq!(
+            run("\n\n", 0..0, LineOperation::RemoveBlank),
+            Some("|".into())
+        );
+        // A selected block takes its line break along.
+        assert_eq!(
+            run("a\n\n\nb", 2..4, LineOperation::RemoveBlank),
+            Some("a\n|b".into())
+        );
+        // A single selected blank line.
+        assert_eq!(
+            run("a\n\nb", 2..2, LineOperation::RemoveBlank),
+            Some("|a\nb".into())
+        );
+    }
+
+    #[test]
+    fn remove_duplicates_keeps_first_occurrences() {
+        assert_eq!(
+            run("b\na\nb\na", 0..0, LineOperation::RemoveDuplicates),
+            Some("|b\na".into())
+        );
+    }
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file9"), Ordering::Greater);
+        assert_eq!(natural_cmp("File1", "file2"), Ordering::Less);
+        assert_eq!(natural_cmp("a007", "a7"), "a007".cmp("a7"));
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("", ""), Ordering::Equal);
+    }
+
+    #[test]
+    fn sorts_by_column() {
+        let edit = sort_by_column("x,3\ny,10\nz,2", 0..0, ",", 2, false).unwrap();
+        assert_eq!(edit.replacement, "z,2\nx,3\ny,10");
+        // Whitespace-separated, and lines without the field sort first.
+        let edit = sort_by_column("b  2\na 1\nc", 0..0, "", 2, false).unwrap();
+        assert_eq!(edit.replacement, "c\na 1\nb  2");
+        assert_eq!(sort_by_column("a 2\nb 1", 0..0, "", 2, true), None);
+    }
+}
@@ -1,7 +1,53 @@
 mod app;
+mod brackets;
+mod case;
+mod cli;
+mod comment;
+mod diff;
 mod editor;
+mod folding;
+mod fonts;
+mod fuzzy;
+mod indent;
+mod keymap;
+mod lines;
+mod paths;
+mod recent;
+mod recovery;
+mod reflow;
 mod search;
+mod session;
+mod settings;
+mod storage;
+mod syntax;
+mod theme;
+mod watch;
 
-fn main() -> Result<(), druid::PlatformError> {
-    app::run()
+use std::process::ExitCode;
+
+use cli::Invocation;
+
+fn main() -> ExitCode {
+    let mut options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Invocation::Launch(options)) => options,
+        Ok(Invocation::Help) => {
+            println!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("rustpad: {err}\n\n{}", cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if let Err(err) = options.load_stdin() {
+        eprintln!("rustpad: {err}");
+        return ExitCode::FAILURE;
+    }
+    match app::run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("rustpad: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+const APP_DIR: &str = "rustpad";
+
+/// Directory for machine-local state that should survive restarts but is not
+/// user configuration (recovery snapshots, session data).
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// Directory for files the user is expected to read and edit (settings).
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
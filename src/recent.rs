@@ -0,0 +1,47 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+use crate::storage::{self, StorageError};
+
+pub const MAX_RECENT_FILES: usize = 10;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RecentFile {
+    files: Vec<PathBuf>,
+}
+
+fn recent_path() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("recent.toml"))
+}
+
+/// Loads the most-recently-used list, dropping files that no longer exist.
+pub fn load() -> Result<Vec<PathBuf>, StorageError> {
+    let path = recent_path().ok_or(StorageError::NoDirectory)?;
+    let stored: RecentFile = storage::load_toml(&path)?.unwrap_or_default();
+    Ok(stored
+        .files
+        .into_iter()
+        .filter(|file| file.exists())
+        .take(MAX_RECENT_FILES)
+        .collect())
+}
+
+pub fn save(files: &[PathBuf]) -> Result<(), StorageError> {
+    let path = recent_path().ok_or(StorageError::NoDirectory)?;
+    storage::save_toml(
+        &path,
+        &RecentFile {
+            files: files.to_vec(),
+        },
+    )
+}
+
+/// Moves `path` to the front of `files`, trimming the list to its maximum length.
+pub fn promote(files: &mut Vec<PathBuf>, path: &Path) {
+    files.retain(|file| file != path);
+    files.insert(0, path.to_owned());
+    files.truncate(MAX_RECENT_FILES);
+}
@@ -0,0 +1,197 @@
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use chrono::Local;
+use druid::Data;
+use thiserror::Error;
+
+use crate::paths;
+
+const HEADER: &str = "rustpad-recovery 1";
+const EXTENSION: &str = "recovery";
+const LOCK_EXTENSION: &str = "lock";
+
+#[derive(Debug, Error)]
+pub enum RecoveryError {
+    #[error("no recovery directory is available on this system")]
+    NoDirectory,
+    #[error("recovery I/O failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("malformed recovery file {}", .0.display())]
+    Malformed(PathBuf),
+}
+
+/// A snapshot found in the recovery directory at startup.
+#[derive(Clone, Data, Debug)]
+pub struct RecoveredDocument {
+    pub id: Arc<String>,
+    pub original_path: Option<Arc<String>>,
+    pub saved_at: Arc<String>,
+    pub text: Arc<String>,
+}
+
+/// Produces an id unique to this process for naming a buffer's snapshot file.
+pub fn new_snapshot_id() -> Arc<String> {
+    static NEXT: AtomicUsize = AtomicUsize::new(1);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    Arc::new(format!(
+        "{}-{}-{n}",
+        Local::now().format("%Y%m%d%H%M%S"),
+        std::process::id()
+    ))
+}
+
+pub fn recovery_dir() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("recovery"))
+}
+
+pub fn write_snapshot(
+    id: &str,
+    original_path: Option<&str>,
+    text: &str,
+) -> Result<(), RecoveryError> {
+    let dir = recovery_dir().ok_or(RecoveryError::NoDirectory)?;
+    fs::create_dir_all(&dir)?;
+    let mut contents = String::with_capacity(text.len() + 128);
+    contents.push_str(HEADER);
+    contents.push('\n');
+    contents.push_str("path: ");
+    contents.push_str(original_path.unwrap_or_default());
+    contents.push('\n');
+    contents.push_str("saved: ");
+    contents.push_str(&Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+    contents.push_str("\n\n");
+    contents.push_str(text);
+
+    // Write next to the target and rename so a crash mid-write never leaves a
+    // truncated snapshot behind. Each write gets its own temp file.
+    static NEXT_TEMP: AtomicUsize = AtomicUsize::new(1);
+    let target = snapshot_path(&dir, id);
+    let temp = dir.join(format!(
+        "{id}.{}.tmp",
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, &target)?;
+    Ok(())
+}
+
+pub fn remove_snapshot(id: &str) -> Result<(), RecoveryError> {
+    let dir = recovery_dir().ok_or(RecoveryError::NoDirectory)?;
+    match fs::remove_file(snapshot_path(&dir, id)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Moves a recovered snapshot under a new id owned by this process, so other
+/// instances see it as live while the restored buffer is open.
+pub fn claim_snapshot(id: &str) -> Result<Arc<String>, RecoveryError> {
+    let dir = recovery_dir().ok_or(RecoveryError::NoDirectory)?;
+    let claimed = new_snapshot_id();
+    fs::rename(snapshot_path(&dir, id), snapshot_path(&dir, &claimed))?;
+    Ok(claimed)
+}
+
+/// Locks this instance's lock file, then reads the snapshots left by
+/// instances that no longer hold theirs. Snapshots of a running instance's
+/// buffers are skipped.
+pub fn load_snapshots() -> Result<Vec<RecoveredDocument>, RecoveryError> {
+    let Some(dir) = recovery_dir() else {
+        return Ok(Vec::new());
+    };
+    lock_instance(&dir)?;
+    let mut documents = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(EXTENSION) {
+            continue;
+        }
+        let pid = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.split('-').nth(1));
+        if pid.is_some_and(|pid| is_running(&dir, pid)) {
+            continue;
+        }
+        match parse_snapshot(&path) {
+            Ok(document) => documents.push(document),
+            // A half-written or foreign file shouldn't block recovering the rest.
+            Err(RecoveryError::Malformed(_)) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    documents.sort_by(|a, b| a.saved_at.cmp(&b.saved_at));
+    Ok(documents)
+}
+
+fn parse_snapshot(path: &Path) -> Result<RecoveredDocument, RecoveryError> {
+    let malformed = || RecoveryError::Malformed(path.to_owned());
+    let raw = fs::read_to_string(path)?;
+    let (header, text) = raw.split_once("\n\n").ok_or_else(malformed)?;
+    let mut lines = header.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(malformed());
+    }
+    let original = lines
+        .next()
+        .and_then(|line| line.strip_prefix("path: "))
+        .ok_or_else(malformed)?;
+    let saved_at = lines
+        .next()
+        .and_then(|line| line.strip_prefix("saved: "))
+        .ok_or_else(malformed)?;
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(malformed)?;
+    Ok(RecoveredDocument {
+        id: Arc::new(id),
+        original_path: (!original.is_empty()).then(|| Arc::new(original.to_string())),
+        saved_at: Arc::new(saved_at.to_string()),
+        text: Arc::new(text.to_string()),
+    })
+}
+
+/// Holds `<pid>.lock` locked until the process exits, marking the snapshots
+/// whose ids carry this pid as in use.
+fn lock_instance(dir: &Path) -> Result<(), RecoveryError> {
+    static LOCK: OnceLock<File> = OnceLock::new();
+    if LOCK.get().is_some() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    let file = File::create(lock_path(dir, &std::process::id().to_string()))?;
+    file.try_lock().map_err(io::Error::from)?;
+    let _ = LOCK.set(file);
+    Ok(())
+}
+
+/// Whether the instance with process id `pid` still holds its lock. A lock
+/// file nobody holds is left over from a crash and is removed.
+fn is_running(dir: &Path, pid: &str) -> bool {
+    let path = lock_path(dir, pid);
+    let Ok(file) = File::open(&path) else {
+        return false;
+    };
+    match file.try_lock() {
+        Ok(()) => {
+            drop(file);
+            let _ = fs::remove_file(&path);
+            false
+        }
+        Err(TryLockError::WouldBlock) => true,
+        Err(TryLockError::Error(_)) => false,
+    }
+}
+
+fn lock_path(dir: &Path, pid: &str) -> PathBuf {
+    dir.join(format!("{pid}.{LOCK_EXTENSION}"))
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.{EXTENSION}"))
+}
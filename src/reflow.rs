@@ -0,0 +1,219 @@
+/// What kind of text is being reflowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// Plain text or Markdown: every paragraph is reflowed, and `>` quote
+    /// markers repeat on each line.
+    Prose,
+    /// Source code: only lines starting with the language's line comment
+    /// marker are reflowed; code is left as it is.
+    Code { line_comment: Option<&'static str> },
+}
+
+/// Reflows `text` so no line is wider than `width` columns where it can be
+/// helped. Paragraphs are separated by blank lines or a change of prefix;
+/// each keeps the indentation and comment prefix of its first line. List
+/// items start a new paragraph and wrap under their text, and Markdown
+/// headings are never joined. A word longer than the width gets a line to
+/// itself.
+pub fn hard_wrap(text: &str, width: usize, style: Style) -> String {
+    let trailing_newline = text.ends_with('\n');
+    let mut output: Vec<String> = Vec::new();
+    let mut paragraph: Option<Paragraph> = None;
+
+    for line in text.lines() {
+        let Some(prefix) = split_prefix(line, style) else {
+            flush(&mut output, paragraph.take(), width);
+            output.push(line.to_string());
+            continue;
+        };
+        let body = &line[prefix.len()..];
+        let content = body.trim_start();
+        if content.is_empty() || (style == Style::Prose && is_heading(content)) {
+            flush(&mut output, paragraph.take(), width);
+            output.push(line.trim_end().to_string());
+            continue;
+        }
+        let lead = &line[..line.len() - content.len()];
+        if let Some(bullet) = bullet_len(content) {
+            flush(&mut output, paragraph.take(), width);
+            let first = &line[..lead.len() + bullet];
+            paragraph = Some(Paragraph {
+                first: first.to_string(),
+                rest: format!("{lead}{}", " ".repeat(bullet)),
+                words: content[bullet..].split_whitespace().collect(),
+            });
+            continue;
+        }
+        match &mut paragraph {
+            Some(current) if current.rest == lead => {
+                current.words.extend(content.split_whitespace())
+            }
+            _ => {
+                flush(&mut output, paragraph.take(), width);
+                paragraph = Some(Paragraph {
+                    first: lead.to_string(),
+                    rest: lead.to_string(),
+                    words: content.split_whitespace().collect(),
+                });
+            }
+        }
+    }
+    flush(&mut output, paragraph, width);
+
+    let mut wrapped = output.join("\n");
+    if trailing_newline {
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+struct Paragraph<'a> {
+    /// Indentation and markers of the first line.
+    first: String,
+    /// What the following lines start with.
+    rest: String,
+    words: Vec<&'a str>,
+}
+
+/// The indentation plus comment or quote markers (with the space after them)
+/// at the start of `line`, or `None` for a line of code that must not be
+/// reflowed.
+fn split_prefix(line: &str, style: Style) -> Option<&str> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let marker = match style {
+        Style::Prose => rest.len() - rest.trim_start_matches(['>', ' ']).len(),
+        Style::Code { line_comment } => {
+            let comment = line_comment.filter(|comment| rest.starts_with(comment))?;
+            // Doc comments such as `///` and `//!` extend the marker.
+            let last = comment.chars().last()?;
+            let extra = rest[comment.len()..].len()
+                - rest[comment.len()..].trim_start_matches([last, '!']).len();
+            let marker = comment.len() + extra;
+            marker + usize::from(rest[marker..].starts_with(' '))
+        }
+    };
+    Some(&line[..indent + marker])
+}
+
+/// Length of a list marker such as `- `, `* `, `+ ` or `12. ` at the start
+/// of `text`, with the space after it.
+fn bullet_len(text: &str) -> Option<usize> {
+    if ["- ", "* ", "+ "]
+        .iter()
+        .any(|bullet| text.starts_with(bullet))
+    {
+        return Some(2);
+    }
+    let digits = text.len()
+        - text
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    let rest = &text[digits..];
+    (digits > 0 && (rest.starts_with(". ") || rest.starts_with(") "))).then_some(digits + 2)
+}
+
+fn is_heading(text: &str) -> bool {
+    let hashes = text.len() - text.trim_start_matches('#').len();
+    (1..=6).contains(&hashes) && text[hashes..].starts_with(' ')
+}
+
+fn flush(output: &mut Vec<String>, paragraph: Option<Paragraph>, width: usize) {
+    let Some(paragraph) = paragraph else {
+        return;
+    };
+    let mut prefix = paragraph.first.as_str();
+    let mut line = String::from(prefix);
+    let mut line_width = prefix.chars().count();
+    let mut empty = true;
+    for word in paragraph.words {
+        let word_width = word.chars().count();
+        if !empty && line_width + 1 + word_width > width {
+            output.push(std::mem::take(&mut line));
+            prefix = paragraph.rest.as_str();
+            line.push_str(prefix);
+            line_width = prefix.chars().count();
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+            line_width += 1;
+        }
+        line.push_str(word);
+        line_width += word_width;
+        empty = false;
+    }
+    output.push(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: Style = Style::Code {
+        line_comment: Some("//"),
+    };
+
+    #[test]
+    fn joins_and_wraps_a_paragraph() {
+        let text = "one two\nthree four five six\n";
+        assert_eq!(
+            hard_wrap(text, 10, Style::Prose),
+            "one two\nthree four\nfive six\n"
+        );
+    }
+
+    #[test]
+    fn keeps_list_items_apart() {
+        assert_eq!(hard_wrap("* a\n* b", 80, Style::Prose), "* a\n* b");
+        assert_eq!(hard_wrap("1. a\n2. b", 80, Style::Prose), "1. a\n2. b");
+    }
+
+    #[test]
+    fn wraps_list_items_under_their_text() {
+        assert_eq!(
+            hard_wrap("- alpha beta gamma\n  delta", 13, Style::Prose),
+            "- alpha beta\n  gamma delta"
+        );
+    }
+
+    #[test]
+    fn keeps_headings_on_their_own_lines() {
+        let text = "# Title\n## Section\nsome text";
+        assert_eq!(hard_wrap(text, 80, Style::Prose), text);
+    }
+
+    #[test]
+    fn repeats_quote_markers() {
+        assert_eq!(
+            hard_wrap("> one two three", 9, Style::Prose),
+            "> one two\n> three"
+        );
+    }
+
+    #[test]
+    fn leaves_code_alone() {
+        let text = "#[derive(Debug)]\n#[serde(default)]\nstruct A;";
+        assert_eq!(hard_wrap(text, 10, RUST), text);
+        let json = Style::Code { line_comment: None };
+        assert_eq!(hard_wrap("{\n  \"a\": 1\n}", 5, json), "{\n  \"a\": 1\n}");
+    }
+
+    #[test]
+    fn wraps_comments_with_their_marker() {
+        let text = "    /// one two three\n    /// four\nfn f() {}\n";
+        assert_eq!(
+            hard_wrap(text, 18, RUST),
+            "    /// one two\n    /// three four\nfn f() {}\n"
+        );
+    }
+
+    #[test]
+    fn uses_the_language_marker() {
+        let python = Style::Code {
+            line_comment: Some("#"),
+        };
+        assert_eq!(hard_wrap("# a\n# b", 80, python), "# a b");
+        assert_eq!(hard_wrap("// a\n// b", 80, python), "// a\n// b");
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+use crate::storage::{self, StorageError};
+
+/// What was open when RustPad last exited.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub active: usize,
+    pub documents: Vec<SessionDocument>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionDocument {
+    pub path: PathBuf,
+    pub selection_start: usize,
+    pub selection_end: usize,
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+}
+
+pub fn session_path() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("session.toml"))
+}
+
+/// Reads the saved session; `Ok(None)` when there is none yet.
+pub fn load() -> Result<Option<Session>, StorageError> {
+    storage::load_toml(&session_path().ok_or(StorageError::NoDirectory)?)
+}
+
+pub fn save(session: &Session) -> Result<(), StorageError> {
+    storage::save_toml(&session_path().ok_or(StorageError::NoDirectory)?, session)
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fonts;
+use crate::paths;
+use crate::storage::{self, StorageError};
+
+/// User preferences, stored as `settings.toml` in the config directory.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub word_wrap: bool,
+    pub show_status_bar: bool,
+    pub show_line_numbers: bool,
+    pub highlight_current_line: bool,
+    pub show_whitespace: bool,
+    /// Columns to draw vertical rulers at; the first is also the hard wrap width.
+    pub rulers: Vec<u16>,
+    pub wrap_at_column: bool,
+    pub wrap_column: u16,
+    /// Columns per tab, and spaces per level when indenting with spaces.
+    pub tab_width: u8,
+    pub insert_spaces: bool,
+    pub theme: String,
+    pub zoom_percent: u16,
+    pub font: FontConfig,
+    pub search: SearchConfig,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            word_wrap: false,
+            show_status_bar: true,
+            show_line_numbers: true,
+            highlight_current_line: true,
+            show_whitespace: false,
+            rulers: vec![80, 100],
+            wrap_at_column: false,
+            wrap_column: 80,
+            tab_width: 4,
+            insert_spaces: true,
+            theme: "Light".to_string(),
+            zoom_percent: 100,
+            font: FontConfig::default(),
+            search: SearchConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    pub family: String,
+    pub size: f64,
+    pub weight: u16,
+    pub italic: bool,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self {
+            family: fonts::FALLBACK_FAMILY.to_string(),
+            size: 15.0,
+            weight: 400,
+            italic: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub match_case: bool,
+    pub search_down: bool,
+    pub wrap_around: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            match_case: false,
+            search_down: true,
+            wrap_around: true,
+        }
+    }
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("settings.toml"))
+}
+
+/// Reads the settings file; `Ok(None)` when the user has never saved one.
+pub fn load() -> Result<Option<Settings>, StorageError> {
+    storage::load_toml(&settings_path().ok_or(StorageError::NoDirectory)?)
+}
+
+pub fn save(settings: &Settings) -> Result<(), StorageError> {
+    storage::save_toml(&settings_path().ok_or(StorageError::NoDirectory)?, settings)
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("no suitable directory is available on this system")]
+    NoDirectory,
+    #[error("{}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("unable to encode {}: {source}", path.display())]
+    Encode {
+        path: PathBuf,
+        source: toml::ser::Error,
+    },
+}
+
+/// Reads a TOML file; `Ok(None)` when it does not exist yet.
+pub fn load_toml<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, StorageError> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(StorageError::Io {
+                path: path.to_owned(),
+                source,
+            });
+        }
+    };
+    toml::from_str(&raw)
+        .map(Some)
+        .map_err(|source| StorageError::Parse {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+pub fn save_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), StorageError> {
+    let encoded = toml::to_string_pretty(value).map_err(|source| StorageError::Encode {
+        path: path.to_owned(),
+        source,
+    })?;
+    let io_err = |source| StorageError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    fs::write(path, encoded).map_err(io_err)
+}
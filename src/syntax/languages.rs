@@ -0,0 +1,132 @@
+use super::lexer::{Delimiter, Spec};
+
+const fn delimiter(open: &'static str, close: &'static str, multiline: bool) -> Delimiter {
+    Delimiter {
+        open,
+        close,
+        multiline,
+        escapes: true,
+    }
+}
+
+pub const RUST: Spec = Spec {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    nested_comments: true,
+    strings: &[delimiter("\"", "\"", true)],
+    char_literals: true,
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while", "yield",
+    ],
+    types: &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ],
+    capitalized_types: true,
+    key_separator: None,
+    sigil: None,
+    attributes: &["#[", "#!["],
+    section_headers: false,
+};
+
+pub const TOML: Spec = Spec {
+    line_comments: &["#"],
+    block_comment: None,
+    nested_comments: false,
+    strings: &[
+        delimiter("\"\"\"", "\"\"\"", true),
+        Delimiter {
+            open: "'''",
+            close: "'''",
+            multiline: true,
+            escapes: false,
+        },
+        delimiter("\"", "\"", false),
+        Delimiter {
+            open: "'",
+            close: "'",
+            multiline: false,
+            escapes: false,
+        },
+    ],
+    char_literals: false,
+    keywords: &["true", "false", "inf", "nan"],
+    types: &[],
+    capitalized_types: false,
+    key_separator: Some('='),
+    sigil: None,
+    attributes: &[],
+    section_headers: true,
+};
+
+pub const JSON: Spec = Spec {
+    line_comments: &[],
+    block_comment: None,
+    nested_comments: false,
+    strings: &[delimiter("\"", "\"", false)],
+    char_literals: false,
+    keywords: &["true", "false", "null"],
+    types: &[],
+    capitalized_types: false,
+    key_separator: Some(':'),
+    sigil: None,
+    attributes: &[],
+    section_headers: false,
+};
+
+pub const SHELL: Spec = Spec {
+    line_comments: &["#"],
+    block_comment: None,
+    nested_comments: false,
+    strings: &[
+        delimiter("\"", "\"", true),
+        Delimiter {
+            open: "'",
+            close: "'",
+            multiline: true,
+            escapes: false,
+        },
+    ],
+    char_literals: false,
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+        "in", "function", "return", "local", "export", "readonly", "break", "continue", "exit",
+        "select", "time",
+    ],
+    types: &[],
+    capitalized_types: false,
+    key_separator: None,
+    sigil: Some('$'),
+    attributes: &[],
+    section_headers: false,
+};
+
+pub const PYTHON: Spec = Spec {
+    line_comments: &["#"],
+    block_comment: None,
+    nested_comments: false,
+    strings: &[
+        delimiter("\"\"\"", "\"\"\"", true),
+        delimiter("'''", "'''", true),
+        delimiter("\"", "\"", false),
+        delimiter("'", "'", false),
+    ],
+    char_literals: false,
+    keywords: &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+        "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+        "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+        "try", "while", "with", "yield", "self",
+    ],
+    types: &[
+        "int", "float", "str", "bytes", "bool", "list", "dict", "set", "tuple", "object",
+    ],
+    capitalized_types: true,
+    key_separator: None,
+    sigil: Some('@'),
+    attributes: &[],
+    section_headers: false,
+};
@@ -0,0 +1,288 @@
+use super::{Span, State, TokenKind};
+
+/// How a C-like language spells its comments, strings and words. One lexer
+/// covers every language described this way.
+pub struct Spec {
+    pub line_comments: &'static [&'static str],
+    pub block_comment: Option<(&'static str, &'static str)>,
+    pub nested_comments: bool,
+    /// Longest delimiters first, so `"""` wins over `"`.
+    pub strings: &'static [Delimiter],
+    /// `'a'` is a character but `'a` on its own is a lifetime.
+    pub char_literals: bool,
+    pub keywords: &'static [&'static str],
+    pub types: &'static [&'static str],
+    /// Words starting with a capital letter are types.
+    pub capitalized_types: bool,
+    /// A word or string followed by this is a key, as in `key = 1`.
+    pub key_separator: Option<char>,
+    /// Marks a variable or decorator name, as in `$HOME` or `@property`.
+    pub sigil: Option<char>,
+    /// Openers of attributes that run to the next `]`, as in `#[derive]`.
+    pub attributes: &'static [&'static str],
+    /// A `[...]` at the start of a line is a section header.
+    pub section_headers: bool,
+}
+
+pub struct Delimiter {
+    pub open: &'static str,
+    pub close: &'static str,
+    pub multiline: bool,
+    pub escapes: bool,
+}
+
+pub fn lex_line(spec: &Spec, line: &str, state: State, spans: &mut Vec<Span>) -> State {
+    let mut i = 0;
+    match state {
+        State::BlockComment(depth) => {
+            let (end, open) = block_comment(spec, line, 0, depth);
+            push(spans, 0, end, TokenKind::Comment);
+            if let Some(depth) = open {
+                return State::BlockComment(depth);
+            }
+            i = end;
+        }
+        State::String(index) => {
+            let delimiter = &spec.strings[usize::from(index)];
+            let (end, closed) = string(delimiter, line, 0);
+            push(spans, 0, end, TokenKind::String);
+            if !closed {
+                return state;
+            }
+            i = end;
+        }
+        State::Normal | State::Fenced => {}
+    }
+
+    while i < line.len() {
+        let rest = &line[i..];
+        let ch = rest.chars().next().unwrap_or_default();
+        if ch.is_whitespace() {
+            i += ch.len_utf8();
+            continue;
+        }
+
+        if let Some(sigil) = spec.sigil
+            && ch == sigil
+        {
+            let end = sigil_name(line, i + ch.len_utf8());
+            if end > i + ch.len_utf8() {
+                push(spans, i, end, TokenKind::Attribute);
+                i = end;
+                continue;
+            }
+        }
+
+        if spec
+            .line_comments
+            .iter()
+            .any(|marker| rest.starts_with(marker))
+        {
+            push(spans, i, line.len(), TokenKind::Comment);
+            break;
+        }
+
+        if let Some((open, _)) = spec.block_comment
+            && rest.starts_with(open)
+        {
+            let (end, open) = block_comment(spec, line, i + open.len(), 1);
+            push(spans, i, end, TokenKind::Comment);
+            if let Some(depth) = open {
+                return State::BlockComment(depth);
+            }
+            i = end;
+            continue;
+        }
+
+        if spec.attributes.iter().any(|open| rest.starts_with(open)) {
+            let end = rest.find(']').map_or(line.len(), |index| i + index + 1);
+            push(spans, i, end, TokenKind::Attribute);
+            i = end;
+            continue;
+        }
+
+        if spec.section_headers && ch == '[' && line[..i].trim().is_empty() {
+            let end = rest.rfind(']').map_or(line.len(), |index| i + index + 1);
+            push(spans, i, end, TokenKind::Heading);
+            i = end;
+            continue;
+        }
+
+        if let Some(index) = spec
+            .strings
+            .iter()
+            .position(|delimiter| rest.starts_with(delimiter.open))
+        {
+            let delimiter = &spec.strings[index];
+            let (end, closed) = string(delimiter, line, i + delimiter.open.len());
+            let kind = if closed && is_key(spec, &line[end..]) {
+                TokenKind::Attribute
+            } else {
+                TokenKind::String
+            };
+            push(spans, i, end, kind);
+            if !closed && delimiter.multiline {
+                return State::String(index as u8);
+            }
+            i = end;
+            continue;
+        }
+
+        if spec.char_literals && ch == '\'' {
+            if let Some(end) = char_literal(line, i) {
+                push(spans, i, end, TokenKind::String);
+                i = end;
+            } else {
+                // A lifetime or label.
+                let end = word_end(line, i + 1);
+                push(spans, i, end, TokenKind::Type);
+                i = end.max(i + 1);
+            }
+            continue;
+        }
+
+        if ch.is_ascii_digit() {
+            let end = number_end(line, i);
+            push(spans, i, end, TokenKind::Number);
+            i = end;
+            continue;
+        }
+
+        if is_word_char(ch) {
+            let end = word_end(line, i);
+            let word = &line[i..end];
+            let kind = if is_key(spec, &line[end..]) {
+                Some(TokenKind::Attribute)
+            } else if spec.keywords.contains(&word) {
+                Some(TokenKind::Keyword)
+            } else if spec.types.contains(&word)
+                || (spec.capitalized_types && word.starts_with(|c: char| c.is_uppercase()))
+            {
+                Some(TokenKind::Type)
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                push(spans, i, end, kind);
+            }
+            i = end;
+            continue;
+        }
+
+        i += ch.len_utf8();
+    }
+    State::Normal
+}
+
+fn push(spans: &mut Vec<Span>, start: usize, end: usize, kind: TokenKind) {
+    if end > start {
+        spans.push(Span { start, end, kind });
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+fn word_end(line: &str, start: usize) -> usize {
+    line[start..]
+        .find(|ch: char| !is_word_char(ch))
+        .map_or(line.len(), |index| start + index)
+}
+
+/// Digits, letters for suffixes and hex, and a decimal point when a digit
+/// follows it (so `0..10` stays a range).
+fn number_end(line: &str, start: usize) -> usize {
+    let bytes = line.as_bytes();
+    let mut end = start;
+    while end < bytes.len() {
+        let byte = bytes[end];
+        let decimal_point =
+            byte == b'.' && bytes.get(end + 1).is_some_and(|next| next.is_ascii_digit());
+        if byte.is_ascii_alphanumeric() || byte == b'_' || decimal_point {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+fn sigil_name(line: &str, start: usize) -> usize {
+    let rest = &line[start..];
+    if rest.starts_with('{') {
+        return rest.find('}').map_or(line.len(), |index| start + index + 1);
+    }
+    // Shell specials such as `$?`, `$#` and `$1`.
+    if let Some(ch) = rest.chars().next()
+        && "?#@*!$-0123456789".contains(ch)
+    {
+        return start + ch.len_utf8();
+    }
+    rest.find(|ch: char| !is_word_char(ch) && ch != '.')
+        .map_or(line.len(), |index| start + index)
+}
+
+fn is_key(spec: &Spec, after: &str) -> bool {
+    spec.key_separator
+        .is_some_and(|separator| after.trim_start().starts_with(separator))
+}
+
+/// Scans a string body from `start`; returns where it ends and whether the
+/// closing delimiter was found on this line.
+fn string(delimiter: &Delimiter, line: &str, start: usize) -> (usize, bool) {
+    let mut i = start;
+    while i < line.len() {
+        let rest = &line[i..];
+        if delimiter.escapes && rest.starts_with('\\') {
+            i += 1;
+            i += line[i..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if rest.starts_with(delimiter.close) {
+            return (i + delimiter.close.len(), true);
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    (line.len(), false)
+}
+
+/// Scans a block comment body from `start` at the given nesting depth;
+/// returns where it ends and the depth still open at the end of the line.
+fn block_comment(spec: &Spec, line: &str, start: usize, depth: u8) -> (usize, Option<u8>) {
+    let Some((open, close)) = spec.block_comment else {
+        return (line.len(), None);
+    };
+    let mut depth = depth;
+    let mut i = start;
+    while i < line.len() {
+        let rest = &line[i..];
+        if rest.starts_with(close) {
+            i += close.len();
+            depth -= 1;
+            if depth == 0 {
+                return (i, None);
+            }
+        } else if spec.nested_comments && rest.starts_with(open) {
+            i += open.len();
+            depth = depth.saturating_add(1);
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    (line.len(), Some(depth))
+}
+
+/// The end of a character literal starting at `start`, such as `'a'` or
+/// `'\n'`; `None` for lifetimes.
+fn char_literal(line: &str, start: usize) -> Option<usize> {
+    let rest = &line[start + 1..];
+    let mut chars = rest.char_indices();
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        let close = rest[1..].find('\'')?;
+        return Some(start + 1 + 1 + close + 1);
+    }
+    let (index, second) = chars.next()?;
+    (second == '\'').then_some(start + 1 + index + 1)
+}
@@ -0,0 +1,126 @@
+use super::{Span, State, TokenKind};
+
+const FENCES: [&str; 2] = ["```", "~~~"];
+
+pub fn lex_line(line: &str, state: State, spans: &mut Vec<Span>) -> State {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let fence = FENCES.iter().any(|fence| trimmed.starts_with(fence));
+
+    if state == State::Fenced || fence {
+        push(spans, 0, line.len(), TokenKind::String);
+        // A fence line both opens and closes a block.
+        return if state == State::Fenced && fence {
+            State::Normal
+        } else {
+            State::Fenced
+        };
+    }
+
+    if is_heading(trimmed) {
+        push(spans, 0, line.len(), TokenKind::Heading);
+        return State::Normal;
+    }
+    if trimmed.starts_with('>') {
+        push(spans, 0, line.len(), TokenKind::Comment);
+        return State::Normal;
+    }
+
+    let marker = list_marker(trimmed);
+    push(spans, indent, indent + marker, TokenKind::Keyword);
+    inline(line, indent + marker, spans);
+    State::Normal
+}
+
+fn push(spans: &mut Vec<Span>, start: usize, end: usize, kind: TokenKind) {
+    if end > start {
+        spans.push(Span { start, end, kind });
+    }
+}
+
+fn is_heading(line: &str) -> bool {
+    let level = line.len() - line.trim_start_matches('#').len();
+    (1..=6).contains(&level) && line[level..].chars().next().is_none_or(char::is_whitespace)
+}
+
+/// Length of a leading `-`, `*`, `+` or `1.` list marker and its space.
+fn list_marker(line: &str) -> usize {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return 2;
+    }
+    let digits = line.len()
+        - line
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        digits + 2
+    } else {
+        0
+    }
+}
+
+/// Code spans, emphasis and links within a line.
+fn inline(line: &str, start: usize, spans: &mut Vec<Span>) {
+    let bytes = line.as_bytes();
+    let mut i = start;
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'`' => line[i + 1..].find('`').map(|index| i + 1 + index + 1),
+            b'*' | b'_' => emphasis(line, i),
+            b'[' => link(line, i),
+            _ => None,
+        };
+        let kind = match bytes[i] {
+            b'`' => TokenKind::String,
+            b'[' => TokenKind::Attribute,
+            _ => TokenKind::Emphasis,
+        };
+        match end {
+            Some(end) => {
+                push(spans, i, end, kind);
+                i = end;
+            }
+            None => i += line[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+}
+
+/// `*em*`, `**strong**` and the underscore forms; an underscore inside a
+/// word (as in `snake_case`) is not emphasis.
+fn emphasis(line: &str, start: usize) -> Option<usize> {
+    let marker = line.as_bytes()[start];
+    if marker == b'_'
+        && line[..start]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric)
+    {
+        return None;
+    }
+    let run = if line.as_bytes().get(start + 1) == Some(&marker) {
+        2
+    } else {
+        1
+    };
+    let delimiter = &line[start..start + run];
+    let body = start + run;
+    if line[body..].starts_with(char::is_whitespace) {
+        return None;
+    }
+    let close = line[body..].find(delimiter)?;
+    (close > 0).then_some(body + close + run)
+}
+
+/// `[text](target)` or `[text][reference]`.
+fn link(line: &str, start: usize) -> Option<usize> {
+    let text_end = start + line[start..].find(']')?;
+    let after = &line[text_end + 1..];
+    let close = match after.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    after[1..]
+        .find(close)
+        .map(|index| text_end + 1 + 1 + index + 1)
+}
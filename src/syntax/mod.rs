@@ -0,0 +1,341 @@
+use std::ops::Range;
+use std::path::Path;
+
+mod languages;
+mod lexer;
+mod markdown;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    Plain,
+    Rust,
+    Markdown,
+    Toml,
+    Json,
+    Shell,
+    Python,
+}
+
+impl Language {
+    /// Picks a language from the file extension; unknown or missing
+    /// extensions are plain text.
+    pub fn from_path(path: Option<&Path>) -> Self {
+        let extension = path
+            .and_then(|path| path.extension())
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("rs") => Self::Rust,
+            Some("md" | "markdown") => Self::Markdown,
+            Some("toml") => Self::Toml,
+            Some("json") => Self::Json,
+            Some("sh" | "bash" | "zsh") => Self::Shell,
+            Some("py" | "pyw") => Self::Python,
+            _ => Self::Plain,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    /// Attributes, decorators, variables and object keys.
+    Attribute,
+    Heading,
+    Emphasis,
+}
+
+/// What a line inherits from the one before it: an unfinished comment,
+/// string or fenced block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Normal,
+    /// Open block comments, counting nesting.
+    BlockComment(u8),
+    /// An open string, by index into the language's string delimiters.
+    String(u8),
+    Fenced,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+struct Line {
+    /// State at the start of the line.
+    state: State,
+    /// Length in bytes, including the newline.
+    len: usize,
+    /// Spans relative to the start of the line.
+    spans: Vec<Span>,
+}
+
+/// Tokens for a whole document, kept per line so an edit only re-lexes the
+/// lines it touched and any after them whose starting state changed.
+pub struct Highlighter {
+    language: Language,
+    lines: Vec<Line>,
+}
+
+impl Highlighter {
+    pub fn new(language: Language, text: &str) -> Self {
+        let mut highlighter = Self {
+            language,
+            lines: Vec::new(),
+        };
+        highlighter.lines = highlighter.lex_from(text, 0, State::Normal, |_, _| false);
+        highlighter
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Brings the tokens from `old` up to date with `new`.
+    pub fn update(&mut self, old: &str, new: &str) {
+        if self.language == Language::Plain {
+            return;
+        }
+        let prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(old.len().min(new.len()) - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        // The first line the edit touched, and where it starts.
+        let mut first = 0;
+        let mut start = 0;
+        while first + 1 < self.lines.len() && start + self.lines[first].len <= prefix {
+            start += self.lines[first].len;
+            first += 1;
+        }
+        let state = self
+            .lines
+            .get(first)
+            .map_or(State::Normal, |line| line.state);
+
+        // Old lines past the edit are reused as soon as the lexer reaches one
+        // at the same text in the same state.
+        let mut old_starts = Vec::with_capacity(self.lines.len() - first);
+        let mut offset = start;
+        for line in &self.lines[first..] {
+            old_starts.push(offset);
+            offset += line.len;
+        }
+        let new_tail = new.len() - suffix;
+        let old_lines = &self.lines;
+        let mut resume = None;
+        let relexed = self.lex_from(new, start, state, |position, state| {
+            if position < new_tail {
+                return false;
+            }
+            let target = position + old.len() - new.len();
+            match old_starts.binary_search(&target) {
+                Ok(index) if old_lines[first + index].state == state => {
+                    resume = Some(first + index);
+                    true
+                }
+                _ => false,
+            }
+        });
+        let tail = match resume {
+            Some(index) => self.lines.split_off(index),
+            None => Vec::new(),
+        };
+        self.lines.truncate(first);
+        self.lines.extend(relexed);
+        self.lines.extend(tail);
+    }
+
+    /// Every span in document order, with offsets into the whole text.
+    pub fn spans(&self) -> impl Iterator<Item = Span> + '_ {
+        let mut start = 0;
+        self.lines.iter().flat_map(move |line| {
+            let line_start = start;
+            start += line.len;
+            line.spans.iter().map(move |span| Span {
+                start: line_start + span.start,
+                end: line_start + span.end,
+                ..*span
+            })
+        })
+    }
+
+    /// Byte ranges of strings and comments, in order.
+    pub fn literals(&self) -> Vec<Range<usize>> {
+        self.spans()
+            .filter(|span| matches!(span.kind, TokenKind::String | TokenKind::Comment))
+            .map(|span| span.start..span.end)
+            .collect()
+    }
+
+    /// Lexes `text` line by line from `start` until the end, or until `stop`
+    /// accepts the position and state of a line about to be lexed.
+    fn lex_from(
+        &self,
+        text: &str,
+        start: usize,
+        mut state: State,
+        mut stop: impl FnMut(usize, State) -> bool,
+    ) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut position = start;
+        loop {
+            if stop(position, state) {
+                break;
+            }
+            let rest = &text[position..];
+            let (content, len) = match rest.find('\n') {
+                Some(index) => (&rest[..index], index + 1),
+                None => (rest, rest.len()),
+            };
+            let mut spans = Vec::new();
+            let next = lex_line(self.language, content, state, &mut spans);
+            lines.push(Line { state, len, spans });
+            state = next;
+            position += len;
+            if len == content.len() {
+                break;
+            }
+        }
+        lines
+    }
+}
+
+fn lex_line(language: Language, line: &str, state: State, spans: &mut Vec<Span>) -> State {
+    match language {
+        Language::Plain => State::Normal,
+        Language::Markdown => markdown::lex_line(line, state, spans),
+        Language::Rust => lexer::lex_line(&languages::RUST, line, state, spans),
+        Language::Toml => lexer::lex_line(&languages::TOML, line, state, spans),
+        Language::Json => lexer::lex_line(&languages::JSON, line, state, spans),
+        Language::Shell => lexer::lex_line(&languages::SHELL, line, state, spans),
+        Language::Python => lexer::lex_line(&languages::PYTHON, line, state, spans),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: &str = "/* block\n   comment */\nfn main() {\n    let s = \"one\ntwo\";\n}\n";
+
+    /// Updating from `old` to `new` must give the same spans as lexing `new`
+    /// from scratch.
+    fn check(language: Language, old: &str, new: &str) {
+        let mut highlighter = Highlighter::new(language, old);
+        highlighter.update(old, new);
+        let fresh = Highlighter::new(language, new);
+        assert_eq!(
+            highlighter.spans().collect::<Vec<_>>(),
+            fresh.spans().collect::<Vec<_>>(),
+            "{old:?} -> {new:?}"
+        );
+    }
+
+    fn edit(text: &str, range: Range<usize>, value: &str) -> String {
+        let mut edited = text.to_string();
+        edited.replace_range(range, value);
+        edited
+    }
+
+    #[test]
+    fn edits_inside_a_block_comment() {
+        check(Language::Rust, RUST, &edit(RUST, 3..8, "note"));
+        check(Language::Rust, RUST, &edit(RUST, 8..9, ""));
+        check(Language::Rust, RUST, &edit(RUST, 12..12, "\nmore\n"));
+    }
+
+    #[test]
+    fn opening_and_closing_block_comments() {
+        // Removing the closing marker comments out the rest of the document.
+        check(Language::Rust, RUST, &edit(RUST, 20..22, ""));
+        // Removing the opening marker turns the comment into code.
+        check(Language::Rust, RUST, &edit(RUST, 0..2, ""));
+        let code = "fn a() {}\nfn b() {}\n";
+        check(Language::Rust, code, &format!("/*{code}"));
+        check(Language::Rust, code, &edit(code, 9..9, " /* x"));
+        check(Language::Rust, "/* /* */ */ fn", "/* /* */ fn");
+    }
+
+    #[test]
+    fn edits_across_a_block_comment() {
+        // From inside the comment to past its end.
+        check(Language::Rust, RUST, &edit(RUST, 12..30, "x"));
+        // From code into the comment.
+        let text = "let a;\n/* b\n */ let c;";
+        check(Language::Rust, text, &edit(text, 4..10, ""));
+        check(Language::Rust, text, &edit(text, 4..10, "\"\n"));
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let quote = RUST.find('"').unwrap();
+        check(Language::Rust, RUST, &edit(RUST, quote..quote + 1, ""));
+        check(
+            Language::Rust,
+            RUST,
+            &edit(RUST, quote + 4..quote + 4, "\"\n"),
+        );
+        check(Language::Rust, RUST, &edit(RUST, quote + 1..quote + 9, ""));
+        let python = "x = '''a\nb'''\ny = 1\n";
+        check(Language::Python, python, &edit(python, 4..7, ""));
+        check(Language::Python, python, &edit(python, 10..13, ""));
+        check(Language::Python, python, &edit(python, 9..9, "'''\n'''"));
+    }
+
+    #[test]
+    fn edits_at_the_ends() {
+        check(Language::Rust, RUST, &format!("// {RUST}"));
+        check(Language::Rust, RUST, &format!("{RUST}/*"));
+        check(Language::Rust, RUST, "");
+        check(Language::Rust, "", RUST);
+        check(Language::Rust, RUST, &RUST[..RUST.len() - 1]);
+    }
+
+    #[test]
+    fn fenced_blocks() {
+        let markdown = "```\ncode\n```\n# Heading\n";
+        check(Language::Markdown, markdown, &edit(markdown, 0..3, ""));
+        check(Language::Markdown, markdown, &edit(markdown, 9..12, ""));
+        check(Language::Markdown, markdown, &format!("```\n{markdown}"));
+    }
+
+    #[test]
+    fn successive_updates() {
+        let mut text = RUST.to_string();
+        let mut highlighter = Highlighter::new(Language::Rust, &text);
+        for (range, value) in [
+            (0..0, "/*"),
+            (0..2, ""),
+            (20..22, ""),
+            (20..20, "*/"),
+            (3..3, "\""),
+        ] {
+            let edited = edit(&text, range, value);
+            highlighter.update(&text, &edited);
+            text = edited;
+            assert_eq!(
+                highlighter.spans().collect::<Vec<_>>(),
+                Highlighter::new(Language::Rust, &text)
+                    .spans()
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+}
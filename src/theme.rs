@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use druid::{Color, Data, Env, Key, theme};
+use serde::Deserialize;
+
+use crate::paths;
+use crate::storage;
+
+pub const STATUS_BAR_BACKGROUND: Key<Color> = Key::new("rustpad.theme.status-bar-background");
+pub const STATUS_BAR_TEXT: Key<Color> = Key::new("rustpad.theme.status-bar-text");
+pub const PANEL_BACKGROUND: Key<Color> = Key::new("rustpad.theme.panel-background");
+pub const BANNER_BACKGROUND: Key<Color> = Key::new("rustpad.theme.banner-background");
+pub const BANNER_TEXT: Key<Color> = Key::new("rustpad.theme.banner-text");
+pub const MUTED_TEXT: Key<Color> = Key::new("rustpad.theme.muted-text");
+pub const SELECTION: Key<Color> = Key::new("rustpad.theme.selection");
+pub const GUTTER_BACKGROUND: Key<Color> = Key::new("rustpad.theme.gutter-background");
+pub const GUTTER_TEXT: Key<Color> = Key::new("rustpad.theme.gutter-text");
+pub const GUTTER_ACTIVE_TEXT: Key<Color> = Key::new("rustpad.theme.gutter-active-text");
+pub const CURRENT_LINE: Key<Color> = Key::new("rustpad.theme.current-line");
+pub const WHITESPACE: Key<Color> = Key::new("rustpad.theme.whitespace");
+pub const RULER: Key<Color> = Key::new("rustpad.theme.ruler");
+pub const BRACKET_MATCH: Key<Color> = Key::new("rustpad.theme.bracket-match");
+pub const SYNTAX_KEYWORD: Key<Color> = Key::new("rustpad.theme.syntax-keyword");
+pub const SYNTAX_TYPE: Key<Color> = Key::new("rustpad.theme.syntax-type");
+pub const SYNTAX_STRING: Key<Color> = Key::new("rustpad.theme.syntax-string");
+pub const SYNTAX_NUMBER: Key<Color> = Key::new("rustpad.theme.syntax-number");
+pub const SYNTAX_COMMENT: Key<Color> = Key::new("rustpad.theme.syntax-comment");
+pub const SYNTAX_ATTRIBUTE: Key<Color> = Key::new("rustpad.theme.syntax-attribute");
+pub const SYNTAX_HEADING: Key<Color> = Key::new("rustpad.theme.syntax-heading");
+pub const SYNTAX_EMPHASIS: Key<Color> = Key::new("rustpad.theme.syntax-emphasis");
+
+/// A named color scheme for the editor and the panels around it.
+#[derive(Clone, Data, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub editor_background: Color,
+    pub text: Color,
+    pub muted_text: Color,
+    pub selection: Color,
+    pub border: Color,
+    pub button: Color,
+    pub status_bar_background: Color,
+    pub status_bar_text: Color,
+    pub search_panel_background: Color,
+    pub info_banner_background: Color,
+    pub info_banner_text: Color,
+    pub gutter_background: Color,
+    pub gutter_text: Color,
+    pub gutter_active_text: Color,
+    pub current_line: Color,
+    pub whitespace: Color,
+    pub ruler: Color,
+    pub bracket_match: Color,
+    pub syntax_keyword: Color,
+    pub syntax_type: Color,
+    pub syntax_string: Color,
+    pub syntax_number: Color,
+    pub syntax_comment: Color,
+    pub syntax_attribute: Color,
+    pub syntax_heading: Color,
+    pub syntax_emphasis: Color,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "Light".to_string(),
+            editor_background: Color::WHITE,
+            text: Color::BLACK,
+            muted_text: Color::grey(0.4),
+            selection: Color::rgb8(0xAD, 0xD6, 0xFF),
+            border: Color::grey(0.6),
+            button: Color::rgb8(0xF0, 0xF0, 0xF0),
+            status_bar_background: Color::grey8(0xF0),
+            status_bar_text: Color::BLACK,
+            search_panel_background: Color::grey8(0xF5),
+            info_banner_background: Color::rgb8(0xFF, 0xF3, 0xC0),
+            info_banner_text: Color::BLACK,
+            gutter_background: Color::grey8(0xF7),
+            gutter_text: Color::grey(0.6),
+            gutter_active_text: Color::BLACK,
+            current_line: Color::rgb8(0xF0, 0xF5, 0xFC),
+            whitespace: Color::grey(0.75),
+            ruler: Color::grey8(0xE0),
+            bracket_match: Color::rgba8(0x00, 0x64, 0x00, 0x30),
+            syntax_keyword: Color::rgb8(0x00, 0x00, 0xFF),
+            syntax_type: Color::rgb8(0x26, 0x7F, 0x99),
+            syntax_string: Color::rgb8(0xA3, 0x15, 0x15),
+            syntax_number: Color::rgb8(0x09, 0x86, 0x58),
+            syntax_comment: Color::rgb8(0x00, 0x80, 0x00),
+            syntax_attribute: Color::rgb8(0x00, 0x10, 0x80),
+            syntax_heading: Color::rgb8(0x80, 0x00, 0x00),
+            syntax_emphasis: Color::rgb8(0xAF, 0x00, 0xDB),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark".to_string(),
+            editor_background: Color::rgb8(0x1E, 0x1E, 0x1E),
+            text: Color::rgb8(0xD4, 0xD4, 0xD4),
+            muted_text: Color::grey(0.55),
+            selection: Color::rgb8(0x26, 0x4F, 0x78),
+            border: Color::grey(0.3),
+            button: Color::rgb8(0x3C, 0x3C, 0x3C),
+            status_bar_background: Color::rgb8(0x00, 0x7A, 0xCC),
+            status_bar_text: Color::WHITE,
+            search_panel_background: Color::rgb8(0x25, 0x25, 0x26),
+            info_banner_background: Color::rgb8(0x4D, 0x42, 0x16),
+            info_banner_text: Color::rgb8(0xF0, 0xE6, 0xC0),
+            gutter_background: Color::rgb8(0x1E, 0x1E, 0x1E),
+            gutter_text: Color::rgb8(0x85, 0x85, 0x85),
+            gutter_active_text: Color::rgb8(0xC6, 0xC6, 0xC6),
+            current_line: Color::rgb8(0x2A, 0x2D, 0x2E),
+            whitespace: Color::rgb8(0x4B, 0x4B, 0x4B),
+            ruler: Color::rgb8(0x3A, 0x3A, 0x3A),
+            bracket_match: Color::rgba8(0x90, 0x90, 0x90, 0x50),
+            syntax_keyword: Color::rgb8(0x56, 0x9C, 0xD6),
+            syntax_type: Color::rgb8(0x4E, 0xC9, 0xB0),
+            syntax_string: Color::rgb8(0xCE, 0x91, 0x78),
+            syntax_number: Color::rgb8(0xB5, 0xCE, 0xA8),
+            syntax_comment: Color::rgb8(0x6A, 0x99, 0x55),
+            syntax_attribute: Color::rgb8(0x9C, 0xDC, 0xFE),
+            syntax_heading: Color::rgb8(0xDC, 0xDC, 0xAA),
+            syntax_emphasis: Color::rgb8(0xC5, 0x86, 0xC0),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "High Contrast".to_string(),
+            editor_background: Color::BLACK,
+            text: Color::WHITE,
+            muted_text: Color::rgb8(0xFF, 0xFF, 0x00),
+            selection: Color::rgb8(0x00, 0x60, 0xFF),
+            border: Color::WHITE,
+            button: Color::BLACK,
+            status_bar_background: Color::BLACK,
+            status_bar_text: Color::WHITE,
+            search_panel_background: Color::BLACK,
+            info_banner_background: Color::rgb8(0xFF, 0xFF, 0x00),
+            info_banner_text: Color::BLACK,
+            gutter_background: Color::BLACK,
+            gutter_text: Color::rgb8(0xC0, 0xC0, 0xC0),
+            gutter_active_text: Color::rgb8(0xFF, 0xFF, 0x00),
+            current_line: Color::grey8(0x26),
+            whitespace: Color::grey8(0x80),
+            ruler: Color::grey8(0x80),
+            bracket_match: Color::rgba8(0xFF, 0xFF, 0x00, 0x60),
+            syntax_keyword: Color::rgb8(0x00, 0xFF, 0xFF),
+            syntax_type: Color::rgb8(0x4E, 0xC9, 0xB0),
+            syntax_string: Color::rgb8(0xCE, 0x91, 0x78),
+            syntax_number: Color::rgb8(0xB5, 0xCE, 0xA8),
+            syntax_comment: Color::rgb8(0x7C, 0xA6, 0x68),
+            syntax_attribute: Color::rgb8(0x9C, 0xDC, 0xFE),
+            syntax_heading: Color::rgb8(0x00, 0xFF, 0x00),
+            syntax_emphasis: Color::rgb8(0xFF, 0x80, 0xFF),
+        }
+    }
+
+    pub fn built_in() -> Vec<Theme> {
+        vec![Self::light(), Self::dark(), Self::high_contrast()]
+    }
+
+    /// Sets the color named by a theme file key; `false` for unknown names.
+    fn set_color(&mut self, name: &str, color: Color) -> bool {
+        let slot = match name {
+            "editor_background" => &mut self.editor_background,
+            "text" => &mut self.text,
+            "muted_text" => &mut self.muted_text,
+            "selection" => &mut self.selection,
+            "border" => &mut self.border,
+            "button" => &mut self.button,
+            "status_bar_background" => &mut self.status_bar_background,
+            "status_bar_text" => &mut self.status_bar_text,
+            "search_panel_background" => &mut self.search_panel_background,
+            "info_banner_background" => &mut self.info_banner_background,
+            "info_banner_text" => &mut self.info_banner_text,
+            "gutter_background" => &mut self.gutter_background,
+            "gutter_text" => &mut self.gutter_text,
+            "gutter_active_text" => &mut self.gutter_active_text,
+            "current_line" => &mut self.current_line,
+            "whitespace" => &mut self.whitespace,
+            "ruler" => &mut self.ruler,
+            "bracket_match" => &mut self.bracket_match,
+            "syntax_keyword" => &mut self.syntax_keyword,
+            "syntax_type" => &mut self.syntax_type,
+            "syntax_string" => &mut self.syntax_string,
+            "syntax_number" => &mut self.syntax_number,
+            "syntax_comment" => &mut self.syntax_comment,
+            "syntax_attribute" => &mut self.syntax_attribute,
+            "syntax_heading" => &mut self.syntax_heading,
+            "syntax_emphasis" => &mut self.syntax_emphasis,
+            _ => return false,
+        };
+        *slot = color;
+        true
+    }
+
+    /// Points druid's own theme keys and ours at this theme's colors.
+    pub fn apply(&self, env: &mut Env) {
+        env.set(theme::WINDOW_BACKGROUND_COLOR, self.editor_background);
+        env.set(theme::BACKGROUND_LIGHT, self.editor_background);
+        env.set(theme::BACKGROUND_DARK, self.editor_background);
+        env.set(theme::TEXT_COLOR, self.text);
+        env.set(theme::CURSOR_COLOR, self.text);
+        env.set(theme::PLACEHOLDER_COLOR, self.muted_text);
+        env.set(theme::DISABLED_TEXT_COLOR, self.muted_text);
+        env.set(theme::SELECTED_TEXT_BACKGROUND_COLOR, self.selection);
+        env.set(
+            theme::SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR,
+            self.selection,
+        );
+        env.set(theme::BORDER_DARK, self.border);
+        env.set(theme::BORDER_LIGHT, self.border);
+        env.set(theme::BUTTON_LIGHT, self.button);
+        env.set(theme::BUTTON_DARK, self.button);
+        env.set(STATUS_BAR_BACKGROUND, self.status_bar_background);
+        env.set(STATUS_BAR_TEXT, self.status_bar_text);
+        env.set(PANEL_BACKGROUND, self.search_panel_background);
+        env.set(BANNER_BACKGROUND, self.info_banner_background);
+        env.set(BANNER_TEXT, self.info_banner_text);
+        env.set(MUTED_TEXT, self.muted_text);
+        env.set(SELECTION, self.selection);
+        env.set(GUTTER_BACKGROUND, self.gutter_background);
+        env.set(GUTTER_TEXT, self.gutter_text);
+        env.set(GUTTER_ACTIVE_TEXT, self.gutter_active_text);
+        env.set(CURRENT_LINE, self.current_line);
+        env.set(WHITESPACE, self.whitespace);
+        env.set(RULER, self.ruler);
+        env.set(BRACKET_MATCH, self.bracket_match);
+        env.set(SYNTAX_KEYWORD, self.syntax_keyword);
+        env.set(SYNTAX_TYPE, self.syntax_type);
+        env.set(SYNTAX_STRING, self.syntax_string);
+        env.set(SYNTAX_NUMBER, self.syntax_number);
+        env.set(SYNTAX_COMMENT, self.syntax_comment);
+        env.set(SYNTAX_ATTRIBUTE, self.syntax_attribute);
+        env.set(SYNTAX_HEADING, self.syntax_heading);
+        env.set(SYNTAX_EMPHASIS, self.syntax_emphasis);
+    }
+}
+
+/// A user theme: colors layered over one of the built-in themes.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    name: String,
+    #[serde(default)]
+    base: Option<String>,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+pub fn themes_dir() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("themes"))
+}
+
+/// Reads every `*.toml` file in the themes directory. Files that fail to
+/// parse are skipped; bad entries in otherwise valid files are ignored.
+/// Either way the problem is described in the returned list.
+pub fn load_user_themes() -> (Vec<Theme>, Vec<String>) {
+    let mut themes = Vec::new();
+    let mut problems = Vec::new();
+    let Some(dir) = themes_dir() else {
+        return (themes, problems);
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return (themes, problems);
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let file: ThemeFile = match storage::load_toml(&path) {
+            Ok(Some(file)) => file,
+            Ok(None) => continue,
+            Err(err) => {
+                problems.push(err.to_string());
+                continue;
+            }
+        };
+        let mut theme = match file.base.as_deref() {
+            None => Theme::light(),
+            Some(base) => match Theme::built_in().into_iter().find(|t| t.name == base) {
+                Some(theme) => theme,
+                None => {
+                    problems.push(format!("{}: unknown base theme \"{base}\"", path.display()));
+                    Theme::light()
+                }
+            },
+        };
+        theme.name = file.name;
+        for (name, value) in &file.colors {
+            match Color::from_hex_str(value) {
+                Ok(color) => {
+                    if !theme.set_color(name, color) {
+                        problems.push(format!("{}: unknown color \"{name}\"", path.display()));
+                    }
+                }
+                Err(_) => problems.push(format!(
+                    "{}: \"{value}\" for {name} is not a #RRGGBB color",
+                    path.display()
+                )),
+            }
+        }
+        themes.push(theme);
+    }
+    (themes, problems)
+}
@@ -0,0 +1,83 @@
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use druid::Data;
+
+/// What we last saw on disk for an open file, used to notice outside edits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    id: Option<(u64, u64)>,
+}
+
+impl Data for FileStamp {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl FileStamp {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        fs::metadata(path).map(|meta| Self::from_metadata(&meta))
+    }
+
+    fn from_metadata(meta: &Metadata) -> Self {
+        Self {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+            id: file_id(meta),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiskChange {
+    Unchanged,
+    Modified,
+    Deleted,
+    Renamed(PathBuf),
+}
+
+/// Compares the file at `path` with the stamp taken when it was last loaded or
+/// saved. A `None` stamp means the file is not expected to exist yet.
+pub fn check(path: &Path, stamp: Option<FileStamp>) -> DiskChange {
+    match (FileStamp::read(path), stamp) {
+        (Ok(current), Some(stamp)) if current == stamp => DiskChange::Unchanged,
+        (Ok(_), _) => DiskChange::Modified,
+        (Err(_), None) => DiskChange::Unchanged,
+        (Err(_), Some(stamp)) => match find_renamed(path, stamp) {
+            Some(new_path) => DiskChange::Renamed(new_path),
+            None => DiskChange::Deleted,
+        },
+    }
+}
+
+/// Looks for the same file under a new name in the original directory, which
+/// covers the common `mv` and "rename in file manager" cases.
+fn find_renamed(path: &Path, stamp: FileStamp) -> Option<PathBuf> {
+    let id = stamp.id?;
+    let dir = path.parent()?;
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|meta| file_id(&meta) == Some(id))
+        })
+        .map(|entry| entry.path())
+}
+
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}